@@ -0,0 +1,265 @@
+use ethers::signers::LocalWallet;
+use ethers::types::U256;
+use serenity::async_trait;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::evm::{Erc20Backend, EvmConnection, NativeBackend};
+use crate::guard::check_evm_chain;
+use crate::lnd::{LndBackend, LndNode};
+use crate::structs::{Coin, CoinKind, Config, LIGHTNING};
+use crate::utils::{claim_id, hash_user, parse_units};
+
+/// The node or network a coin is paid out through
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub enum Endpoint {
+    Lnd(String),
+    Evm(String),
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Lnd(name) => write!(f, "lnd {}", name),
+            Endpoint::Evm(name) => write!(f, "evm {}", name),
+        }
+    }
+}
+
+/// The claim a payout is for, so its transaction can be tied back to it
+#[derive(Clone)]
+pub struct ClaimTag {
+    pub id: String,
+    pub coin: String,
+    pub user: String, // hashed
+}
+
+impl ClaimTag {
+    pub fn new(user_id: u64, coin: &str, timestamp: u64, secret: &str) -> Self {
+        ClaimTag {
+            id: claim_id(user_id, coin, timestamp),
+            coin: coin.to_owned(),
+            user: hash_user(secret, user_id),
+        }
+    }
+}
+
+/// What a successful payout handed back
+pub enum Sent {
+    /// Broadcast on chain, still has to confirm
+    Transaction { txid: String, fee: Option<U256> },
+    /// Settled lightning payment, final as is
+    Payment {
+        hash: String,
+        preimage: String,
+        amount: U256,
+    },
+}
+
+/// Where a sent payout stands on chain
+pub enum Confirmation {
+    Pending,
+    Unknown, // the node has no record of it (yet)
+    Confirmed(u64),
+    Reverted(u64),
+}
+
+/// A transaction that raised the fee of a stuck payout
+pub struct Bumped {
+    pub txid: String,
+    /// Whether it replaced the payout (same nonce) or pays for it as a child (CPFP)
+    pub replaces: bool,
+}
+
+/// Everything the faucet needs to pay out one coin
+#[async_trait]
+pub trait PaymentBackend: Send + Sync {
+    fn coin(&self) -> &Coin;
+
+    fn endpoint(&self) -> Endpoint;
+
+    /// Sends the coin's configured amount to `address` for `claim`
+    async fn send(&self, address: &str, claim: &ClaimTag) -> Result<Sent, Error>;
+
+    async fn confirmation(&self, txid: &str) -> Result<Confirmation, Error>;
+
+    /// How long a payout may stay pending before its fee is bumped, None never bumps
+    fn bump_after(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Raises the fee of the pending payout `txid`, None if it can't be bumped (any further)
+    async fn bump(&self, _txid: &str) -> Result<Option<Bumped>, Error> {
+        Ok(None)
+    }
+
+    /// Faucet balance in the coin's base units
+    async fn balance(&self) -> Result<U256, Error>;
+
+    /// Balance of whatever pays the fees when that isn't the coin itself, ready to print
+    async fn gas_balance(&self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    /// Cheap local check, done before a cooldown slot is reserved
+    fn validate_address(&self, address: &str) -> Result<(), Error>;
+
+    /// Size of the channels opened on request, None if the coin can't open any
+    fn channel_capacity(&self) -> Option<U256> {
+        None
+    }
+
+    /// Opens a channel to the node at `uri` (pubkey@host:port) and returns its channel point
+    async fn open_channel(&self, _uri: &str) -> Result<String, Error> {
+        Err(Error::ChannelsUnsupported(self.coin().name.to_owned()))
+    }
+
+    /// Confirmed once the channel at `channel_point` is active, Unknown if it's gone
+    async fn channel_status(&self, _channel_point: &str) -> Result<Confirmation, Error> {
+        Err(Error::ChannelsUnsupported(self.coin().name.to_owned()))
+    }
+
+    fn explorer_url(&self, txid: &str) -> Option<String> {
+        let template = self.coin().explorer_tx.as_ref()?;
+        Some(template.replace("{txid}", txid))
+    }
+
+    fn explorer_address_url(&self, address: &str) -> Option<String> {
+        let template = self.coin().explorer_address.as_ref()?;
+        Some(template.replace("{address}", address))
+    }
+}
+
+pub type Backends = HashMap<String, Box<dyn PaymentBackend>>; // coin -> backend
+
+pub struct Registry {
+    pub backends: Backends,
+    pub lnd_nodes: HashMap<String, Arc<LndNode>>, // node -> client
+    pub evm_networks: HashMap<String, Arc<EvmConnection>>, // network -> connection
+}
+
+pub async fn build_backends(config: &Config, eth_wallet: &LocalWallet) -> Result<Registry, Error> {
+    let mut backends: Backends = HashMap::new();
+    let mut lnd_nodes: HashMap<String, Arc<LndNode>> = HashMap::new();
+    let mut connections: HashMap<String, Arc<EvmConnection>> = HashMap::new();
+
+    if !config.lnd.is_empty() && config.label_secret.is_empty() {
+        return Err(Error::Lnd(String::from(
+            "set label_secret, payouts are labelled with hashed requester ids",
+        )));
+    }
+
+    for (name, config_lnd) in config.lnd.iter() {
+        let node = LndNode::new(name.to_owned(), config_lnd.clone(), config.allow_mainnet)?;
+        lnd_nodes.insert(name.to_owned(), Arc::new(node));
+    }
+
+    for (name, config_coin) in config.coins.iter() {
+        // templates set on the coin win over the ones of its network
+        let config_network = config.networks.get(&config_coin.network);
+        let explorer_tx = config_coin
+            .explorer_tx
+            .clone()
+            .or_else(|| config_network.and_then(|network| network.explorer_tx.clone()));
+        let explorer_address = config_coin
+            .explorer_address
+            .clone()
+            .or_else(|| config_network.and_then(|network| network.explorer_address.clone()));
+
+        let coin = Coin {
+            name: name.to_owned(),
+            amount: parse_units(&config_coin.amount, config_coin.decimals)?,
+            decimals: config_coin.decimals,
+            contract: config_coin.contract.to_owned(),
+            network: config_coin.network.to_owned(),
+            kind: config_coin.kind,
+            explorer_tx,
+            explorer_address,
+        };
+
+        match (coin.kind, coin.contract.is_empty()) {
+            (CoinKind::Native, false) => {
+                return Err(Error::InvalidCoin(format!(
+                    "{} is native but has a contract",
+                    name
+                )));
+            }
+            (CoinKind::Erc20, true) => {
+                return Err(Error::InvalidCoin(format!(
+                    "{} is erc20 but has no contract",
+                    name
+                )));
+            }
+            _ => {}
+        }
+
+        let backend: Box<dyn PaymentBackend> = if coin.network == LIGHTNING {
+            if coin.kind != CoinKind::Native {
+                return Err(Error::InvalidCoin(format!(
+                    "{} is on lightning but not native",
+                    name
+                )));
+            }
+
+            let node_name = config_coin.node.as_ref().ok_or_else(|| {
+                Error::InvalidCoin(format!("{} is on lightning but has no node", name))
+            })?;
+            let node = lnd_nodes
+                .get(node_name)
+                .cloned()
+                .ok_or_else(|| Error::UnknownNode(node_name.to_owned()))?;
+
+            Box::new(LndBackend::new(coin, node))
+        } else {
+            if config_coin.node.is_some() {
+                return Err(Error::InvalidCoin(format!(
+                    "{} has a node but is not on lightning",
+                    name
+                )));
+            }
+
+            let config_network =
+                config_network.ok_or_else(|| Error::UnknownNetwork(coin.network.to_owned()))?;
+            check_evm_chain(
+                config_network.chain_id,
+                config_network.testnet,
+                config.allow_mainnet,
+            )?;
+
+            let connection = match connections.get(&coin.network) {
+                Some(connection) => connection.clone(),
+                None => {
+                    let connection = Arc::new(EvmConnection::new(
+                        coin.network.to_owned(),
+                        config_network.clone(),
+                        eth_wallet.clone(),
+                        config.allow_mainnet,
+                    )?);
+                    // connect up front, a failure here is retried on first use
+                    if let Err(error) = connection.client().await {
+                        let message = format!("Couldn't connect to {}: {}", coin.network, error);
+                        println!("{}", connection.redact(&message));
+                    }
+                    connections.insert(coin.network.to_owned(), connection.clone());
+                    connection
+                }
+            };
+
+            match coin.kind {
+                CoinKind::Native => Box::new(NativeBackend::new(coin, connection)),
+                CoinKind::Erc20 => Box::new(Erc20Backend::new(coin, connection)?),
+            }
+        };
+
+        backends.insert(name.to_owned(), backend);
+    }
+
+    Ok(Registry {
+        backends,
+        lnd_nodes,
+        evm_networks: connections,
+    })
+}
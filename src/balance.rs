@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::types::U256;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+
+use crate::backend::{Backends, PaymentBackend};
+use crate::utils::format_units;
+use crate::Faucet;
+
+async fn balance_line(backend: &dyn PaymentBackend) -> String {
+    let coin = backend.coin();
+
+    let balance = match backend.balance().await {
+        Ok(balance) => format!("{} {}", format_units(balance, coin.decimals), coin.name),
+        Err(error) => {
+            println!("Couldn't get {} balance: {}", coin.name, error);
+            String::from("unavailable")
+        }
+    };
+
+    match backend.gas_balance().await {
+        Ok(Some(gas)) => format!("{}: {} (gas {})", coin.name, balance, gas),
+        Ok(None) => format!("{}: {}", coin.name, balance),
+        Err(error) => {
+            println!("Couldn't get {} gas balance: {}", coin.name, error);
+            format!("{}: {} (gas unavailable)", coin.name, balance)
+        }
+    }
+}
+
+impl Faucet {
+    /// One line per coin with what the faucet has left to give away
+    pub async fn balances(&self) -> String {
+        let mut names: Vec<&String> = self.backends.keys().collect();
+        names.sort();
+
+        let mut lines = Vec::new();
+        for name in names {
+            lines.push(balance_line(self.backends[name].as_ref()).await);
+        }
+
+        if lines.is_empty() {
+            return String::from("No coins available!");
+        }
+
+        format!("Faucet balance:\n{}", lines.join("\n"))
+    }
+}
+
+/// Periodically checks every coin and posts to the admin channel when one can pay out
+/// fewer than `payouts` more claims, and again once it got refilled.
+pub async fn monitor(
+    backends: Arc<Backends>,
+    http: Arc<Http>,
+    channel: ChannelId,
+    payouts: u64,
+    interval: Duration,
+) {
+    let mut low: HashSet<String> = HashSet::new();
+
+    loop {
+        for (name, backend) in backends.iter() {
+            let coin = backend.coin();
+
+            let balance = match backend.balance().await {
+                Ok(balance) => balance,
+                Err(error) => {
+                    println!("Couldn't get {} balance: {}", name, error);
+                    continue;
+                }
+            };
+
+            let threshold = coin.amount.saturating_mul(U256::from(payouts));
+
+            let message = if balance < threshold {
+                if !low.insert(name.to_owned()) {
+                    continue;
+                }
+                format!(
+                    "{} faucet is running low: {} {} left, that's less than {} payouts!",
+                    name,
+                    format_units(balance, coin.decimals),
+                    name,
+                    payouts
+                )
+            } else {
+                if !low.remove(name) {
+                    continue;
+                }
+                format!(
+                    "{} faucet got refilled: {} {}",
+                    name,
+                    format_units(balance, coin.decimals),
+                    name
+                )
+            };
+
+            if let Err(why) = channel.say(&http, &message).await {
+                println!("Error sending balance alert: {:?}", why);
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
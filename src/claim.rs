@@ -0,0 +1,204 @@
+use serenity::model::prelude::UserId;
+
+use crate::backend::{ClaimTag, Sent};
+use crate::errors::Error;
+use crate::tracker::{Payout, PayoutKind};
+use crate::utils::format_units;
+use crate::Faucet;
+
+fn wait_message(remaining: u64, what: &str) -> String {
+    let hours = remaining / 3600;
+    let mins = (remaining - hours * 3600) / 60;
+    format!(
+        "Please wait another {}h{}m before requesting new {}!",
+        hours, mins, what
+    )
+}
+
+impl Faucet {
+    /// Handles a faucet request and returns the text to reply with,
+    /// plus the payout to track if one went out
+    pub async fn claim(
+        &self,
+        user_id: UserId,
+        coin_name: &str,
+        address: &str,
+    ) -> (String, Option<Payout>) {
+        let coin_name = coin_name.replace(' ', "").to_uppercase(); // allow spaces and lowercase coin
+        let address = address.replace(' ', "");
+
+        let backend = match self.backends.get(&coin_name) {
+            Some(backend) => backend,
+            None => return ("Coin not supported!".to_string(), None),
+        };
+
+        if backend.validate_address(&address).is_err() {
+            return (String::from("Invalid address!"), None);
+        }
+
+        let reservation = match self.reserve(user_id, &coin_name, self.config.limit).await {
+            Ok(reservation) => reservation,
+            Err(remaining) => return (wait_message(remaining, &coin_name), None),
+        };
+
+        // the claim has to be on disk before anything goes out, a crash mustn't forget it
+        if let Err(error) = self.commit(&reservation).await {
+            println!("Failed to persist claim: {}", error);
+            return (
+                String::from("Couldn't record the claim, retry later!"),
+                None,
+            );
+        }
+
+        let claim = ClaimTag::new(
+            user_id.0,
+            &coin_name,
+            reservation.timestamp,
+            &self.config.label_secret,
+        );
+
+        match backend.send(&address, &claim).await {
+            Ok(Sent::Transaction { txid, fee }) => {
+                let payout = Payout {
+                    user_id,
+                    coin: coin_name.to_owned(),
+                    kind: PayoutKind::Transaction,
+                    txid: txid.to_owned(),
+                    cooldown: coin_name.to_owned(),
+                    timestamp: reservation.timestamp,
+                };
+                self.paid(&reservation, &txid).await;
+
+                let explorer = backend.explorer_url(&txid).unwrap_or(txid);
+                let recipient = backend
+                    .explorer_address_url(&address)
+                    .unwrap_or_else(|| address.to_owned());
+
+                let coin = backend.coin();
+                let mut text = format!(
+                    "Sent {} {} to {}! {}",
+                    format_units(coin.amount, coin.decimals),
+                    coin.name,
+                    recipient,
+                    explorer
+                );
+                if let Some(fee) = fee {
+                    text += &format!(" (fee {} {})", format_units(fee, coin.decimals), coin.name);
+                }
+                (text, Some(payout))
+            }
+            Ok(Sent::Payment {
+                hash,
+                preimage,
+                amount,
+            }) => {
+                let coin = backend.coin();
+                let text = format!(
+                    "Paid {} {} over lightning! payment hash {}, preimage {}",
+                    format_units(amount, coin.decimals),
+                    coin.name,
+                    hash,
+                    preimage
+                );
+                (text, None)
+            }
+            Err(error) => {
+                self.rollback(reservation).await;
+
+                println!("{}", error);
+                let text = match error {
+                    Error::InvalidAddress => String::from("Invalid address!"),
+                    Error::NoFunds => String::from("Faucet out of funds!"),
+                    Error::Reverted(_) => String::from("Transfer would fail, retry later!"),
+                    Error::InvoiceRejected(reason) => format!("Invoice rejected, {}!", reason),
+                    Error::FeeTooHigh { .. } => {
+                        String::from("Network fees are too high right now, retry later!")
+                    }
+                    _ => String::from("Transaction failed, retry later!"),
+                };
+                (text, None)
+            }
+        }
+    }
+
+    /// Handles a channel request to the node at `uri` (pubkey@host:port),
+    /// channels have a cooldown of their own next to the coin's
+    pub async fn request_channel(
+        &self,
+        user_id: UserId,
+        coin_name: &str,
+        uri: &str,
+    ) -> (String, Option<Payout>) {
+        let coin_name = coin_name.replace(' ', "").to_uppercase();
+        let uri = uri.replace(' ', "");
+
+        let backend = match self.backends.get(&coin_name) {
+            Some(backend) => backend,
+            None => return ("Coin not supported!".to_string(), None),
+        };
+
+        let capacity = match backend.channel_capacity() {
+            Some(capacity) => capacity,
+            None => return (format!("No channels for {}!", coin_name), None),
+        };
+
+        let cooldown = format!("{} channel", coin_name);
+        let reservation = match self
+            .reserve(user_id, &cooldown, self.config.channel_limit)
+            .await
+        {
+            Ok(reservation) => reservation,
+            Err(remaining) => return (wait_message(remaining, &cooldown), None),
+        };
+
+        if let Err(error) = self.commit(&reservation).await {
+            println!("Failed to persist claim: {}", error);
+            return (
+                String::from("Couldn't record the claim, retry later!"),
+                None,
+            );
+        }
+
+        match backend.open_channel(&uri).await {
+            Ok(channel_point) => {
+                let payout = Payout {
+                    user_id,
+                    coin: coin_name.to_owned(),
+                    kind: PayoutKind::Channel,
+                    txid: channel_point.to_owned(),
+                    cooldown,
+                    timestamp: reservation.timestamp,
+                };
+
+                let txid = channel_point
+                    .split(':')
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned();
+                self.paid(&reservation, &txid).await;
+                let explorer = backend.explorer_url(&txid).unwrap_or(txid);
+
+                let coin = backend.coin();
+                let text = format!(
+                    "Opening a {} {} channel to {}! Funding tx {}",
+                    format_units(capacity, coin.decimals),
+                    coin.name,
+                    uri,
+                    explorer
+                );
+                (text, Some(payout))
+            }
+            Err(error) => {
+                self.rollback(reservation).await;
+
+                println!("{}", error);
+                let text = match error {
+                    Error::InvalidAddress => String::from("Invalid node, use pubkey@host:port!"),
+                    Error::NoFunds => String::from("Faucet out of funds!"),
+                    _ => String::from("Channel open failed, retry later!"),
+                };
+                (text, None)
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use serenity::builder::CreateApplicationCommands;
+use serenity::model::application::command::{Command, CommandOptionType};
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use std::time::Instant;
+
+use crate::tracker::Reply;
+use crate::Faucet;
+
+// discord refuses more than 25 autocomplete suggestions
+const MAX_CHOICES: usize = 25;
+
+fn option_str<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+}
+
+fn is_admin(command: &ApplicationCommandInteraction) -> bool {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.administrator())
+}
+
+impl Faucet {
+    fn create_commands<'a>(
+        &self,
+        commands: &'a mut CreateApplicationCommands,
+    ) -> &'a mut CreateApplicationCommands {
+        commands.create_application_command(|command| {
+            command
+                .name("faucet")
+                .description("Request testnet coins")
+                .create_option(|option| {
+                    option
+                        .name("coin")
+                        .description("Coin to receive")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_option(|option| {
+                    option
+                        .name("address")
+                        .description("Address to send the coins to, or a lightning invoice")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        });
+        commands.create_application_command(|command| {
+            command
+                .name("balance")
+                .description("Show what the faucet has left")
+        });
+
+        if self.channel_coins().next().is_some() {
+            commands.create_application_command(|command| {
+                command
+                    .name("channel")
+                    .description("Request a lightning channel to your node")
+                    .create_option(|option| {
+                        option
+                            .name("coin")
+                            .description("Coin of the channel")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("node")
+                            .description("Your node as pubkey@host:port")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            });
+        }
+
+        if self.maintained_nodes() {
+            commands.create_application_command(|command| {
+                command
+                    .name("maintain")
+                    .description("Fan out and consolidate the LND wallets now")
+                    .default_member_permissions(Permissions::ADMINISTRATOR)
+                    .dm_permission(false)
+            });
+        }
+
+        if !self.lnd_nodes.is_empty() {
+            commands.create_application_command(|command| {
+                command
+                    .name("reconcile")
+                    .description("Match the LND wallet history against the claims file")
+                    .default_member_permissions(Permissions::ADMINISTRATOR)
+                    .dm_permission(false)
+            });
+        }
+
+        commands
+    }
+
+    fn maintained_nodes(&self) -> bool {
+        self.lnd_nodes.values().any(|node| {
+            node.config.fanout_outputs.is_some() || node.config.consolidate_below_sat.is_some()
+        })
+    }
+
+    fn channel_coins(&self) -> impl Iterator<Item = &String> {
+        self.backends
+            .iter()
+            .filter(|(_, backend)| backend.channel_capacity().is_some())
+            .map(|(name, _)| name)
+    }
+
+    /// Registers the slash commands in the configured guild, or globally when none is set
+    pub async fn register_commands(&self, context: &Context) -> Result<(), serenity::Error> {
+        match self.config.guild_id {
+            Some(guild_id) => {
+                GuildId(guild_id)
+                    .set_application_commands(&context.http, |commands| {
+                        self.create_commands(commands)
+                    })
+                    .await?;
+            }
+            None => {
+                Command::set_global_application_commands(&context.http, |commands| {
+                    self.create_commands(commands)
+                })
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn run_command(&self, context: &Context, command: ApplicationCommandInteraction) {
+        let created = Instant::now();
+
+        if !["faucet", "balance", "channel", "maintain", "reconcile"]
+            .contains(&command.data.name.as_str())
+        {
+            return;
+        }
+
+        // payouts can take longer than the 3s discord gives us to answer
+        if let Err(why) = command
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                    .interaction_response_data(|data| data.ephemeral(true))
+            })
+            .await
+        {
+            println!("Error deferring interaction: {:?}", why);
+            return;
+        }
+
+        let options = &command.data.options;
+        let (response_msg, payout) = match command.data.name.as_str() {
+            "balance" => (self.balances().await, None),
+            // server settings can hand the command to anyone, check again
+            "maintain" | "reconcile" if !is_admin(&command) => (String::from("Admins only!"), None),
+            "maintain" => (self.maintenance().await, None),
+            "reconcile" => (self.reconcile().await, None),
+            "channel" => match (option_str(options, "coin"), option_str(options, "node")) {
+                (Some(coin_name), Some(uri)) => {
+                    self.request_channel(command.user.id, coin_name, uri).await
+                }
+                _ => (String::from("Missing coin or node!"), None),
+            },
+            _ => match (option_str(options, "coin"), option_str(options, "address")) {
+                (Some(coin_name), Some(address)) => {
+                    self.claim(command.user.id, coin_name, address).await
+                }
+                _ => (String::from("Missing coin or address!"), None),
+            },
+        };
+
+        if let Err(why) = command
+            .edit_original_interaction_response(&context.http, |response| {
+                response.content(&response_msg)
+            })
+            .await
+        {
+            println!("Error sending message: {:?}", why);
+            return;
+        }
+
+        if let Some(payout) = payout {
+            let reply = Reply::Interaction { command, created };
+            self.track(&context.http, reply, response_msg, payout).await;
+        }
+    }
+
+    pub async fn autocomplete(&self, context: &Context, autocomplete: AutocompleteInteraction) {
+        let typed = option_str(&autocomplete.data.options, "coin")
+            .unwrap_or_default()
+            .to_uppercase();
+
+        let mut coins: Vec<&String> = if autocomplete.data.name == "channel" {
+            self.channel_coins()
+                .filter(|name| name.starts_with(&typed))
+                .collect()
+        } else {
+            self.backends
+                .keys()
+                .filter(|name| name.starts_with(&typed))
+                .collect()
+        };
+        coins.sort();
+
+        if let Err(why) = autocomplete
+            .create_autocomplete_response(&context.http, |response| {
+                for coin in coins.into_iter().take(MAX_CHOICES) {
+                    response.add_string_choice(coin, coin);
+                }
+                response
+            })
+            .await
+        {
+            println!("Error sending autocomplete: {:?}", why);
+        }
+    }
+}
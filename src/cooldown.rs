@@ -0,0 +1,138 @@
+use serenity::model::prelude::UserId;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::Error;
+use crate::storage::Record;
+use crate::Faucet;
+
+/// A claimed (user, coin) slot, held while the payout is in flight
+pub struct Reservation {
+    pub user_id: UserId,
+    pub coin: String,
+    pub timestamp: u64,
+    previous: u64,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+impl Faucet {
+    /// Atomically claims the user's slot for a coin.
+    /// The cache lock is only held for the check, never across the payout itself.
+    /// Returns the remaining seconds if the user is still cooling down.
+    pub async fn reserve(
+        &self,
+        user_id: UserId,
+        coin: &str,
+        limit_hours: u64,
+    ) -> Result<Reservation, u64> {
+        let timestamp = now();
+        let limit = limit_hours * 3600;
+
+        let mut cache = self.cache.lock().await;
+        let last = cache
+            .entry(user_id)
+            .or_default()
+            .entry(coin.to_owned())
+            .or_default();
+
+        // check if enough hours had elapsed from last request
+        if timestamp <= *last + limit {
+            return Err(*last + limit - timestamp);
+        }
+
+        let previous = *last;
+        *last = timestamp;
+
+        Ok(Reservation {
+            user_id,
+            coin: coin.to_owned(),
+            timestamp,
+            previous,
+        })
+    }
+
+    /// Writes the reservation to the claims file, before anything is paid out for it.
+    /// The slot is given back if that fails, nothing may be sent then.
+    pub async fn commit(&self, reservation: &Reservation) -> Result<(), Error> {
+        let record = Record::Claim {
+            user_id: reservation.user_id.0,
+            coin: reservation.coin.to_owned(),
+            timestamp: reservation.timestamp,
+        };
+
+        let result = self.storage.lock().await.append(&record);
+        if result.is_err() {
+            self.restore(reservation).await;
+        }
+        result
+    }
+
+    /// Records the transaction that paid out a committed claim
+    pub async fn paid(&self, reservation: &Reservation, txid: &str) {
+        let record = Record::Paid {
+            user_id: reservation.user_id.0,
+            coin: reservation.coin.to_owned(),
+            timestamp: reservation.timestamp,
+            txid: txid.to_owned(),
+        };
+
+        if let Err(error) = self.storage.lock().await.append(&record) {
+            println!("Failed to persist txid {}: {}", txid, error);
+        }
+    }
+
+    /// Gives the slot of a committed claim back after its payout failed
+    pub async fn rollback(&self, reservation: Reservation) {
+        self.restore(&reservation).await;
+
+        let record = Record::Release {
+            user_id: reservation.user_id.0,
+            coin: reservation.coin,
+            timestamp: reservation.timestamp,
+        };
+
+        if let Err(error) = self.storage.lock().await.append(&record) {
+            println!("Failed to persist release: {}", error);
+        }
+    }
+
+    async fn restore(&self, reservation: &Reservation) {
+        let mut cache = self.cache.lock().await;
+        if let Some(last) = cache
+            .get_mut(&reservation.user_id)
+            .and_then(|user| user.get_mut(&reservation.coin))
+        {
+            if *last == reservation.timestamp {
+                *last = reservation.previous;
+            }
+        }
+    }
+
+    /// Lifts the cooldown of a committed claim whose payout never went through
+    pub async fn release(&self, user_id: UserId, coin: &str, timestamp: u64) {
+        {
+            let mut cache = self.cache.lock().await;
+            if let Some(user) = cache.get_mut(&user_id) {
+                // a newer claim keeps its cooldown
+                if user.get(coin) == Some(&timestamp) {
+                    user.remove(coin);
+                }
+            }
+        }
+
+        let record = Record::Release {
+            user_id: user_id.0,
+            coin: coin.to_owned(),
+            timestamp,
+        };
+
+        if let Err(error) = self.storage.lock().await.append(&record) {
+            println!("Failed to persist release: {}", error);
+        }
+    }
+}
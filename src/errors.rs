@@ -1,3 +1,6 @@
+use ethers::prelude::signer::SignerMiddlewareError;
+use ethers::prelude::ContractError;
+use ethers::providers::ProviderError;
 use ethers::signers::LocalWallet;
 
 use crate::json_rpc::EvmJsonRpcClient;
@@ -14,8 +17,35 @@ pub enum Error {
     InvalidAddress,
     #[error("Insufficient funds")]
     NoFunds,
+    #[error("Fee rate of {rate} sat/vbyte is above the {cap} sat/vbyte cap")]
+    FeeTooHigh { rate: u64, cap: u64 },
+    #[error("Channels not supported: {0}")]
+    ChannelsUnsupported(String),
+    #[error("Invoice rejected: {0}")]
+    InvoiceRejected(String),
+    #[error("Payment failed: {0}")]
+    PaymentFailed(String),
+    #[error("Transaction reverted: {0}")]
+    Reverted(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
     #[error("Invalid provider url: {0}")]
     InvalidProviderUrl(String),
+    #[error("Invalid coin config: {0}")]
+    InvalidCoin(String),
+    #[error("Unknown network: {0}")]
+    UnknownNetwork(String),
+    #[error("Unknown lnd node: {0}")]
+    UnknownNode(String),
+    #[error("Refusing to send on mainnet: {0}")]
+    Mainnet(String),
+    #[error("Wrong chain id, expected {expected} but provider is on {actual}")]
+    WrongChain { expected: u64, actual: u64 },
+    #[error("Provider error: {0}")]
+    Provider(#[from] ProviderError),
+    // a transport error with the provider's API key redacted from its text
+    #[error("{0}")]
+    ProviderConnection(String),
     #[error("Http client error: {0}")]
     HttpClient(#[from] ethers::providers::HttpClientError),
     #[error("Ws client error: {0}")]
@@ -38,6 +68,38 @@ pub enum Error {
             >,
         >,
     ),
+    #[error("Storage error: {0}")]
+    Storage(#[from] std::io::Error),
+    #[error("Lnd rejected the macaroon: {0}")]
+    LndUnauthorized(String),
+    #[error("Lnd error {code}: {message}")]
+    LndRpc { code: i32, message: String },
     #[error("Lnd error: {0}")]
     Lnd(String),
 }
+
+impl Error {
+    /// True when the provider connection itself broke, as opposed to the node rejecting the request
+    pub fn is_connection_error(&self) -> bool {
+        let provider_error = match self {
+            Error::WsClient(_) | Error::HttpClient(_) | Error::ProviderConnection(_) => {
+                return true
+            }
+            Error::Provider(error) => error,
+            Error::SignerMiddleware(SignerMiddlewareError::MiddlewareError(error)) => error,
+            Error::Contract(ContractError::MiddlewareError {
+                e: SignerMiddlewareError::MiddlewareError(error),
+            }) => error,
+            Error::Contract(ContractError::ProviderError { e }) => e,
+            _ => return false,
+        };
+
+        match provider_error {
+            ProviderError::JsonRpcClientError(error) => {
+                !error.is_error_response() && error.as_serde_error().is_none()
+            }
+            ProviderError::HTTPError(_) => true,
+            _ => false,
+        }
+    }
+}
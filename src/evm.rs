@@ -0,0 +1,622 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::contract::{ContractError, EthError};
+use ethers::prelude::SignerMiddleware;
+use ethers::providers::Http;
+use ethers::providers::Middleware;
+use ethers::providers::Provider;
+use ethers::providers::Ws;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::Address;
+use ethers::types::Eip1559TransactionRequest;
+use ethers::types::U256;
+use ethers::types::{BlockNumber, Bytes, TransactionRequest, H256};
+use serenity::async_trait;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::backend::{Bumped, ClaimTag, Confirmation, Endpoint, PaymentBackend, Sent};
+use crate::errors::Error;
+use crate::guard::check_evm_chain;
+use crate::ierc20::IERC20;
+use crate::json_rpc::EvmJsonRpcClient;
+use crate::structs::{Coin, ConfigNetwork, FeeMode};
+use crate::utils::{format_units, parse_units, redact_url};
+
+pub type EvmClient = SignerMiddleware<Provider<EvmJsonRpcClient>, LocalWallet>;
+
+async fn connect(provider_url: &str) -> Result<Provider<EvmJsonRpcClient>, Error> {
+    let provider = if provider_url.starts_with("http") {
+        let http_client = Http::from_str(provider_url)
+            .map_err(|_| Error::InvalidProviderUrl(provider_url.to_string()))?;
+        Provider::new(EvmJsonRpcClient::Http(http_client))
+    } else if provider_url.starts_with("ws") {
+        let ws = Ws::connect(provider_url).await?;
+        Provider::new(EvmJsonRpcClient::Ws(ws))
+    } else {
+        return Err(Error::InvalidProviderUrl(provider_url.to_string()));
+    };
+
+    Ok(provider)
+}
+
+/// `error` with the API keys of `providers` taken out, so it can be logged by whoever gets it
+fn redact_error(error: Error, providers: &[String]) -> Error {
+    let text = error.to_string();
+    let redacted = providers
+        .iter()
+        .fold(text.clone(), |text, url| redact_url(&text, url));
+
+    if redacted == text {
+        return error;
+    }
+    // only transport errors carry the url, so the redacted one is still a connection error
+    Error::ProviderConnection(redacted)
+}
+
+/// One long-lived signer client per configured EVM network, shared by all of its coins.
+/// The websocket transport reconnects on its own a few times; once it gives up
+/// the client is dropped here and rebuilt on next use, trying the providers in order.
+pub struct EvmConnection {
+    pub name: String,
+    pub network: ConfigNetwork,
+    allow_mainnet: bool,
+    bump_max_fee: U256, // wei
+    wallet: LocalWallet,
+    client: RwLock<Option<Arc<EvmClient>>>,
+    // next nonce to hand out, None until synced with the node
+    nonce: Mutex<Option<U256>>,
+}
+
+impl EvmConnection {
+    pub fn new(
+        name: String,
+        network: ConfigNetwork,
+        wallet: LocalWallet,
+        allow_mainnet: bool,
+    ) -> Result<Self, Error> {
+        let bump_max_fee = parse_units(&network.bump_max_fee, 18).map_err(|_| {
+            Error::InvalidAmount(format!("bump_max_fee {} of {}", network.bump_max_fee, name))
+        })?;
+
+        Ok(EvmConnection {
+            name,
+            network,
+            allow_mainnet,
+            bump_max_fee,
+            wallet,
+            client: RwLock::new(None),
+            nonce: Mutex::new(None),
+        })
+    }
+
+    async fn connect(&self) -> Result<EvmClient, Error> {
+        let mut last_error = Error::InvalidProviderUrl(format!("no providers for {}", self.name));
+
+        for provider_url in self.network.providers.iter() {
+            let provider = match connect(provider_url).await {
+                Ok(provider) => provider,
+                Err(error) => {
+                    let message = format!("Couldn't connect to {}: {}", provider_url, error);
+                    println!("{}", redact_url(&message, provider_url));
+                    last_error = redact_error(error, &self.network.providers);
+                    continue;
+                }
+            };
+
+            let chain_id = match provider.get_chainid().await {
+                Ok(chain_id) => chain_id.as_u64(),
+                Err(error) => {
+                    let message = format!("Couldn't get chain id from {}: {}", provider_url, error);
+                    println!("{}", redact_url(&message, provider_url));
+                    last_error = redact_error(Error::Provider(error), &self.network.providers);
+                    continue;
+                }
+            };
+
+            if chain_id != self.network.chain_id {
+                println!(
+                    "{} is on the wrong chain!",
+                    redact_url(provider_url, provider_url)
+                );
+                last_error = Error::WrongChain {
+                    expected: self.network.chain_id,
+                    actual: chain_id,
+                };
+                continue;
+            }
+            // the signer is bound to this chain id, so nothing signed here replays elsewhere
+            check_evm_chain(chain_id, self.network.testnet, self.allow_mainnet)?;
+
+            let wallet = self.wallet.clone().with_chain_id(chain_id);
+            return Ok(SignerMiddleware::new(provider, wallet));
+        }
+
+        Err(last_error)
+    }
+
+    /// Checks every configured provider on its own, independent of the cached client
+    pub async fn probe(&self) -> Vec<(String, Result<String, Error>)> {
+        let mut results = Vec::new();
+
+        for provider_url in self.network.providers.iter() {
+            let result = async {
+                let provider = connect(provider_url).await?;
+
+                let chain_id = provider.get_chainid().await?.as_u64();
+                if chain_id != self.network.chain_id {
+                    return Err(Error::WrongChain {
+                        expected: self.network.chain_id,
+                        actual: chain_id,
+                    });
+                }
+                check_evm_chain(chain_id, self.network.testnet, self.allow_mainnet)?;
+
+                let block = provider.get_block_number().await?;
+
+                Ok(format!("chain {}, block {}", chain_id, block))
+            }
+            .await;
+
+            let result = result.map_err(|error| redact_error(error, &self.network.providers));
+            results.push((provider_url.to_owned(), result));
+        }
+
+        results
+    }
+
+    pub async fn client(&self) -> Result<Arc<EvmClient>, Error> {
+        if let Some(client) = self.client.read().await.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let mut guard = self.client.write().await;
+        // another request may have reconnected while we waited for the lock
+        if let Some(client) = guard.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let client = Arc::new(self.connect().await?);
+        *guard = Some(client.clone());
+
+        Ok(client)
+    }
+
+    /// Passes `result` through with provider keys redacted, forgetting `client` if its
+    /// connection broke
+    async fn check<T>(
+        &self,
+        client: &Arc<EvmClient>,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => redact_error(error, &self.network.providers),
+        };
+
+        if error.is_connection_error() {
+            println!("Lost connection to {}: {}", self.name, error);
+            let mut guard = self.client.write().await;
+            if guard
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(current, client))
+            {
+                *guard = None;
+            }
+        }
+        Err(error)
+    }
+
+    /// `text` without the API keys of any of the network's providers
+    pub fn redact(&self, text: &str) -> String {
+        self.network
+            .providers
+            .iter()
+            .fold(text.to_owned(), |text, url| redact_url(&text, url))
+    }
+
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Builds a transaction in the network's configured fee mode
+    fn transaction(&self, to: Address, value: U256, data: Option<Bytes>) -> TypedTransaction {
+        match self.network.fee_mode {
+            FeeMode::Eip1559 => {
+                let mut tx = Eip1559TransactionRequest::new().to(to).value(value);
+                tx.data = data;
+                tx.into()
+            }
+            FeeMode::Legacy => {
+                let mut tx = TransactionRequest::new().to(to).value(value);
+                tx.data = data;
+                tx.into()
+            }
+        }
+    }
+
+    /// Signs and broadcasts `tx` with a locally assigned nonce.
+    /// Payouts on the same network queue up on the nonce lock, so bursts go out
+    /// back to back instead of racing each other for the same nonce.
+    pub async fn send(&self, mut tx: TypedTransaction) -> Result<H256, Error> {
+        let mut nonce = self.nonce.lock().await;
+        let client = self.client().await?;
+
+        let next = match *nonce {
+            Some(next) => next,
+            None => {
+                let result = client
+                    .get_transaction_count(self.address(), Some(BlockNumber::Pending.into()))
+                    .await
+                    .map_err(Error::from);
+                self.check(&client, result).await?
+            }
+        };
+        tx.set_nonce(next);
+
+        let result = client
+            .send_transaction(tx, None)
+            .await
+            .map(|pending_tx| pending_tx.tx_hash())
+            .map_err(Error::from);
+
+        // after a failure we can't tell whether the nonce got used, ask the node again next time
+        *nonce = match result {
+            Ok(_) => Some(next + 1),
+            Err(_) => None,
+        };
+
+        self.check(&client, result).await
+    }
+
+    /// Looks up the receipt of a payout sent through `send`
+    pub async fn confirmation(&self, txid: &str) -> Result<Confirmation, Error> {
+        let tx_hash = parse_hash(txid)?;
+        let client = self.client().await?;
+
+        let result = client
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(Error::from);
+        let receipt = self.check(&client, result).await?;
+
+        if let Some(receipt) = receipt {
+            let block = match receipt.block_number {
+                Some(block) => block.as_u64(),
+                None => return Ok(Confirmation::Pending),
+            };
+            return Ok(match receipt.status {
+                Some(status) if status.is_zero() => Confirmation::Reverted(block),
+                _ => Confirmation::Confirmed(block),
+            });
+        }
+
+        let result = client.get_transaction(tx_hash).await.map_err(Error::from);
+        if self.check(&client, result).await?.is_some() {
+            return Ok(Confirmation::Pending);
+        }
+
+        // the nonce it used may be free again, don't leave a gap behind it
+        *self.nonce.lock().await = None;
+        Ok(Confirmation::Unknown)
+    }
+
+    fn bump_after(&self) -> Option<Duration> {
+        let minutes = self.network.bump_after?;
+        Some(Duration::from_secs(minutes * 60))
+    }
+
+    /// Re-sends the pending `txid` under its nonce with fees raised by a quarter, or to what
+    /// the network asks for now if that's more. Returns None once the replacement
+    /// would cost more than the network's bump_max_fee at its gas limit.
+    pub async fn bump(&self, txid: &str) -> Result<Option<Bumped>, Error> {
+        let tx_hash = parse_hash(txid)?;
+        let client = self.client().await?;
+
+        let result = client.get_transaction(tx_hash).await.map_err(Error::from);
+        let tx = match self.check(&client, result).await? {
+            Some(tx) if tx.block_number.is_none() => tx,
+            _ => return Ok(None),
+        };
+
+        // nodes only accept a replacement that raises every fee by at least a tenth
+        let raise = |fee: U256| fee + fee / 4;
+
+        let (mut replacement, max_fee): (TypedTransaction, U256) = match tx.max_fee_per_gas {
+            Some(max_fee) => {
+                let result = client
+                    .estimate_eip1559_fees(None)
+                    .await
+                    .map_err(Error::from);
+                let (estimate, priority_estimate) = self.check(&client, result).await?;
+
+                let max_fee = raise(max_fee).max(estimate);
+                let priority = raise(tx.max_priority_fee_per_gas.unwrap_or_default())
+                    .max(priority_estimate)
+                    .min(max_fee);
+                let mut request = Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(max_fee)
+                    .max_priority_fee_per_gas(priority);
+                request.to = tx.to.map(Into::into);
+                (request.into(), max_fee)
+            }
+            None => {
+                let result = client.get_gas_price().await.map_err(Error::from);
+                let estimate = self.check(&client, result).await?;
+
+                let gas_price = raise(tx.gas_price.unwrap_or_default()).max(estimate);
+                let mut request = TransactionRequest::new().gas_price(gas_price);
+                request.to = tx.to.map(Into::into);
+                (request.into(), gas_price)
+            }
+        };
+
+        if tx.gas * max_fee > self.bump_max_fee {
+            println!(
+                "Not bumping {}, {} {} in fees is above the {} cap",
+                txid,
+                format_units(tx.gas * max_fee, 18),
+                self.network.native_symbol,
+                self.network.bump_max_fee
+            );
+            return Ok(None);
+        }
+
+        replacement.set_value(tx.value);
+        replacement.set_data(tx.input);
+        replacement.set_gas(tx.gas);
+        replacement.set_nonce(tx.nonce);
+
+        let result = client
+            .send_transaction(replacement, None)
+            .await
+            .map(|pending_tx| pending_tx.tx_hash())
+            .map_err(Error::from);
+        let tx_hash = self.check(&client, result).await?;
+
+        Ok(Some(Bumped {
+            txid: format!("0x{}", hex::encode(tx_hash.as_bytes())),
+            replaces: true,
+        }))
+    }
+}
+
+fn parse_hash(txid: &str) -> Result<H256, Error> {
+    let bytes = hex::decode(txid.trim_start_matches("0x"))?;
+    if bytes.len() != 32 {
+        return Err(Error::HexDecode(hex::FromHexError::InvalidStringLength));
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+fn parse_address(address: &str) -> Result<Address, Error> {
+    Address::from_str(address).map_err(|_| Error::InvalidAddress)
+}
+
+/// Pays out the network's own coin, e.g. ETH on an ethereum testnet
+pub struct NativeBackend {
+    coin: Coin,
+    connection: Arc<EvmConnection>,
+}
+
+impl NativeBackend {
+    pub fn new(coin: Coin, connection: Arc<EvmConnection>) -> Self {
+        NativeBackend { coin, connection }
+    }
+}
+
+#[async_trait]
+impl PaymentBackend for NativeBackend {
+    fn coin(&self) -> &Coin {
+        &self.coin
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::Evm(self.connection.name.to_owned())
+    }
+
+    async fn send(&self, to_address: &str, _claim: &ClaimTag) -> Result<Sent, Error> {
+        let to_address = parse_address(to_address)?;
+
+        let tx = self
+            .connection
+            .transaction(to_address, self.coin.amount, None);
+
+        let tx_hash = self.connection.send(tx).await?;
+
+        Ok(Sent::Transaction {
+            txid: format!("0x{}", hex::encode(tx_hash.as_bytes())),
+            fee: None,
+        })
+    }
+
+    async fn confirmation(&self, txid: &str) -> Result<Confirmation, Error> {
+        self.connection.confirmation(txid).await
+    }
+
+    fn bump_after(&self) -> Option<Duration> {
+        self.connection.bump_after()
+    }
+
+    async fn bump(&self, txid: &str) -> Result<Option<Bumped>, Error> {
+        self.connection.bump(txid).await
+    }
+
+    async fn balance(&self) -> Result<U256, Error> {
+        let client = self.connection.client().await?;
+
+        let result = client
+            .get_balance(self.connection.address(), None)
+            .await
+            .map_err(Error::from);
+        self.connection.check(&client, result).await
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), Error> {
+        parse_address(address).map(|_| ())
+    }
+}
+
+/// Pays out an ERC20 token through its `transfer` method
+pub struct Erc20Backend {
+    coin: Coin,
+    contract: Address,
+    connection: Arc<EvmConnection>,
+}
+
+impl Erc20Backend {
+    pub fn new(coin: Coin, connection: Arc<EvmConnection>) -> Result<Self, Error> {
+        let contract = parse_address(&coin.contract).map_err(|_| {
+            Error::InvalidCoin(format!("{} has an invalid contract address", coin.name))
+        })?;
+
+        Ok(Erc20Backend {
+            coin,
+            contract,
+            connection,
+        })
+    }
+
+    /// Turns the result of an `eth_call` of `transfer` into NoFunds or Reverted
+    async fn preflight(
+        &self,
+        client: &Arc<EvmClient>,
+        result: Result<bool, ContractError<EvmClient>>,
+    ) -> Result<(), Error> {
+        let reason = match result {
+            Ok(true) => return Ok(()),
+            Ok(false) => String::from("transfer returned false"),
+            Err(ContractError::Revert(data)) => String::decode_with_selector(&data)
+                .unwrap_or_else(|| format!("0x{}", hex::encode(&data))),
+            Err(error) => {
+                let error = Error::from(error);
+                if error.is_connection_error() {
+                    return self.connection.check(client, Err(error)).await;
+                }
+                // some nodes answer a revert without any data, just an error message
+                error.to_string()
+            }
+        };
+
+        // most reverts are the faucet running dry, tell those apart
+        if self.balance().await? < self.coin.amount {
+            return Err(Error::NoFunds);
+        }
+
+        Err(Error::Reverted(reason))
+    }
+}
+
+#[async_trait]
+impl PaymentBackend for Erc20Backend {
+    fn coin(&self) -> &Coin {
+        &self.coin
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::Evm(self.connection.name.to_owned())
+    }
+
+    async fn send(&self, to_address: &str, _claim: &ClaimTag) -> Result<Sent, Error> {
+        let to_address = parse_address(to_address)?;
+
+        let client = self.connection.client().await?;
+
+        let erc20_contract = IERC20::new(self.contract, client.clone());
+        let contract_tx = erc20_contract.transfer(to_address, self.coin.amount);
+
+        // dry run first, a paused token or an empty faucet would only burn gas on chain
+        let result = contract_tx
+            .clone()
+            .from(self.connection.address())
+            .call()
+            .await;
+        self.preflight(&client, result).await?;
+
+        let tx = self.connection.transaction(
+            self.contract,
+            U256::zero(),
+            contract_tx.tx.data().cloned(),
+        );
+
+        let tx_hash = self.connection.send(tx).await?;
+
+        Ok(Sent::Transaction {
+            txid: format!("0x{}", hex::encode(tx_hash.as_bytes())),
+            fee: None,
+        })
+    }
+
+    async fn confirmation(&self, txid: &str) -> Result<Confirmation, Error> {
+        self.connection.confirmation(txid).await
+    }
+
+    fn bump_after(&self) -> Option<Duration> {
+        self.connection.bump_after()
+    }
+
+    async fn bump(&self, txid: &str) -> Result<Option<Bumped>, Error> {
+        self.connection.bump(txid).await
+    }
+
+    async fn balance(&self) -> Result<U256, Error> {
+        let client = self.connection.client().await?;
+
+        let erc20_contract = IERC20::new(self.contract, client.clone());
+
+        let result = erc20_contract
+            .balance_of(self.connection.address())
+            .call()
+            .await
+            .map_err(Error::from);
+        self.connection.check(&client, result).await
+    }
+
+    async fn gas_balance(&self) -> Result<Option<String>, Error> {
+        let client = self.connection.client().await?;
+
+        let result = client
+            .get_balance(self.connection.address(), None)
+            .await
+            .map_err(Error::from);
+        let balance = self.connection.check(&client, result).await?;
+
+        Ok(Some(format!(
+            "{} {}",
+            format_units(balance, 18),
+            self.connection.network.native_symbol
+        )))
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), Error> {
+        parse_address(address).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::ProviderError;
+
+    #[tokio::test]
+    async fn redacts_keys_from_http_errors() {
+        let url = "http://127.0.0.1:1/v3/secretkey123";
+        let error = reqwest::get(url).await.unwrap_err();
+        let error = Error::Provider(ProviderError::HTTPError(error));
+        assert!(error.to_string().contains("secretkey123"));
+
+        let error = redact_error(error, &[url.to_owned()]);
+        assert!(matches!(error, Error::ProviderConnection(_)));
+        assert!(error.is_connection_error());
+        assert!(!error.to_string().contains("secretkey123"));
+        assert!(error.to_string().contains("127.0.0.1:1"));
+    }
+
+    #[test]
+    fn keeps_errors_without_keys() {
+        let error = redact_error(Error::NoFunds, &[String::from("http://127.0.0.1:1/v3/key")]);
+        assert!(matches!(error, Error::NoFunds));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::errors::Error;
+use crate::lightning_structs::GetInfoResponse;
+
+// chain ids of well known EVM testnets, anything else is treated as a mainnet
+const EVM_TESTNETS: &[u64] = &[
+    5,         // goerli
+    97,        // bnb smart chain testnet
+    300,       // zksync era sepolia
+    338,       // cronos testnet
+    1337,      // local dev chains (ganache, geth --dev)
+    4002,      // fantom testnet
+    5003,      // mantle sepolia
+    10200,     // gnosis chiado
+    17000,     // holesky
+    31337,     // local dev chains (hardhat, anvil)
+    43113,     // avalanche fuji
+    44787,     // celo alfajores
+    59141,     // linea sepolia
+    80001,     // polygon mumbai
+    80002,     // polygon amoy
+    84532,     // base sepolia
+    421614,    // arbitrum sepolia
+    534351,    // scroll sepolia
+    560048,    // hoodi
+    11155111,  // sepolia
+    11155420,  // optimism sepolia
+    168587773, // blast sepolia
+    999999999, // zora sepolia
+];
+
+// chain ids of well known EVM mainnets, refused even on networks declared as testnet
+const EVM_MAINNETS: &[u64] = &[
+    1,       // ethereum
+    10,      // optimism
+    25,      // cronos
+    56,      // bnb smart chain
+    100,     // gnosis
+    137,     // polygon
+    250,     // fantom
+    324,     // zksync era
+    1101,    // polygon zkevm
+    5000,    // mantle
+    8453,    // base
+    42161,   // arbitrum one
+    42170,   // arbitrum nova
+    42220,   // celo
+    43114,   // avalanche c-chain
+    59144,   // linea
+    81457,   // blast
+    534352,  // scroll
+    7777777, // zora
+];
+
+const LND_TESTNETS: &[&str] = &["testnet", "testnet4", "signet", "regtest", "simnet"];
+
+/// Only known testnets pass, or chains their network declares `testnet = true` for
+/// as long as they aren't a known mainnet
+pub fn check_evm_chain(chain_id: u64, testnet: bool, allow_mainnet: bool) -> Result<(), Error> {
+    if allow_mainnet || EVM_TESTNETS.contains(&chain_id) {
+        return Ok(());
+    }
+
+    if EVM_MAINNETS.contains(&chain_id) {
+        return Err(Error::Mainnet(format!("evm chain id {}", chain_id)));
+    }
+    if !testnet {
+        return Err(Error::Mainnet(format!(
+            "evm chain id {} is not a known testnet, set testnet = true on its network if it is one",
+            chain_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Every chain LND reports has to be a test network
+pub fn check_lnd_network(info: &GetInfoResponse, allow_mainnet: bool) -> Result<(), Error> {
+    if allow_mainnet {
+        return Ok(());
+    }
+
+    if info.chains.is_empty() {
+        return Err(Error::Mainnet(String::from("lnd reported no chains")));
+    }
+
+    for chain in info.chains.iter() {
+        if !LND_TESTNETS.contains(&chain.network.as_str()) {
+            return Err(Error::Mainnet(format!(
+                "lnd {} {}",
+                chain.chain, chain.network
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning_structs::Chain;
+
+    fn info(networks: &[&str]) -> GetInfoResponse {
+        GetInfoResponse {
+            chains: networks
+                .iter()
+                .map(|network| Chain {
+                    chain: String::from("bitcoin"),
+                    network: network.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn refuses_evm_mainnets() {
+        for chain_id in [1, 10, 137, 8453, 42161] {
+            assert!(matches!(
+                check_evm_chain(chain_id, false, false),
+                Err(Error::Mainnet(_))
+            ));
+            // declaring a known mainnet as testnet doesn't help
+            assert!(check_evm_chain(chain_id, true, false).is_err());
+        }
+    }
+
+    #[test]
+    fn allows_evm_testnets() {
+        for chain_id in [11155111, 421614, 84532, 17000, 31337] {
+            assert!(check_evm_chain(chain_id, false, false).is_ok());
+        }
+    }
+
+    #[test]
+    fn unknown_evm_chains_need_testnet_declared() {
+        assert!(check_evm_chain(123456789, false, false).is_err());
+        assert!(check_evm_chain(123456789, true, false).is_ok());
+    }
+
+    #[test]
+    fn allow_mainnet_overrides_evm_check() {
+        assert!(check_evm_chain(1, false, true).is_ok());
+        assert!(check_evm_chain(123456789, false, true).is_ok());
+    }
+
+    #[test]
+    fn refuses_lnd_mainnet() {
+        assert!(matches!(
+            check_lnd_network(&info(&["mainnet"]), false),
+            Err(Error::Mainnet(_))
+        ));
+        assert!(check_lnd_network(&info(&["mainnet"]), true).is_ok());
+    }
+
+    #[test]
+    fn refuses_lnd_without_chains() {
+        assert!(check_lnd_network(&info(&[]), false).is_err());
+    }
+
+    #[test]
+    fn refuses_lnd_mixed_chains() {
+        assert!(check_lnd_network(&info(&["testnet", "mainnet"]), false).is_err());
+    }
+
+    #[test]
+    fn allows_lnd_testnets() {
+        assert!(check_lnd_network(&info(&["testnet"]), false).is_ok());
+        assert!(check_lnd_network(&info(&["signet", "regtest"]), false).is_ok());
+    }
+}
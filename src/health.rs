@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use crate::backend::{Endpoint, Registry};
+use crate::errors::Error;
+use crate::guard::check_lnd_network;
+use crate::utils::redact_url;
+
+struct Probe {
+    endpoint: Endpoint,
+    url: String,
+    result: Result<String, Error>,
+}
+
+/// Calls every LND node and EVM provider once at boot and prints a status table.
+/// Coins behind an endpoint that is down get disabled, or startup is aborted when `strict`.
+pub async fn check(registry: &mut Registry, strict: bool, allow_mainnet: bool) {
+    let mut probes = Vec::new();
+    let mut down: HashSet<Endpoint> = HashSet::new();
+
+    for node in registry.lnd_nodes.values() {
+        let endpoint = Endpoint::Lnd(node.name.to_owned());
+
+        let result = node.get_info().await.and_then(|info| {
+            check_lnd_network(&info, allow_mainnet)?;
+
+            let chains: Vec<String> = info
+                .chains
+                .iter()
+                .map(|chain| format!("{} {}", chain.chain, chain.network))
+                .collect();
+            let synced = if info.synced_to_chain {
+                ""
+            } else {
+                ", not synced"
+            };
+            Ok(format!(
+                "{}, block {}{}",
+                chains.join(", "),
+                info.block_height,
+                synced
+            ))
+        });
+
+        if result.is_err() {
+            down.insert(endpoint.clone());
+        }
+
+        probes.push(Probe {
+            endpoint,
+            url: node.config.url.to_owned(),
+            result,
+        });
+    }
+
+    for connection in registry.evm_networks.values() {
+        let endpoint = Endpoint::Evm(connection.name.to_owned());
+
+        // providers are fallbacks for each other, the network is only down if all of them are
+        let results = connection.probe().await;
+        if results.iter().all(|(_, result)| result.is_err()) {
+            down.insert(endpoint.clone());
+        }
+
+        for (url, result) in results {
+            probes.push(Probe {
+                endpoint: endpoint.clone(),
+                url,
+                result,
+            });
+        }
+    }
+
+    // stable sort, providers of a network stay in their fallback order
+    probes.sort_by_key(|probe| probe.endpoint.to_string());
+    print_table(&probes);
+
+    if down.is_empty() {
+        return;
+    }
+
+    let mut disabled: Vec<String> = registry
+        .backends
+        .iter()
+        .filter(|(_, backend)| down.contains(&backend.endpoint()))
+        .map(|(name, _)| name.to_owned())
+        .collect();
+    disabled.sort();
+
+    if strict {
+        panic!(
+            "Startup checks failed, refusing to start! Affected coins: {}",
+            disabled.join(", ")
+        );
+    }
+
+    for name in disabled.iter() {
+        registry.backends.remove(name);
+    }
+    println!("Disabled coins: {}", disabled.join(", "));
+}
+
+fn print_table(probes: &[Probe]) {
+    let rows: Vec<(String, String, String)> = probes
+        .iter()
+        .map(|probe| {
+            let status = match &probe.result {
+                Ok(details) => format!("ok ({})", details),
+                Err(error) => format!("FAILED ({})", error),
+            };
+            (
+                probe.endpoint.to_string(),
+                redact_url(&probe.url, &probe.url),
+                redact_url(&status, &probe.url),
+            )
+        })
+        .collect();
+
+    let endpoint_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0).max(8);
+    let url_width = rows
+        .iter()
+        .map(|row| row.1.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    println!(
+        "{:<endpoint_width$}  {:<url_width$}  STATUS",
+        "ENDPOINT", "URL"
+    );
+    for (endpoint, url, status) in rows {
+        println!(
+            "{:<endpoint_width$}  {:<url_width$}  {}",
+            endpoint, url, status
+        );
+    }
+}
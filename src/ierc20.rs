@@ -6,6 +6,7 @@ pub use ierc20::*;
     clippy::too_many_arguments,
     clippy::upper_case_acronyms,
     clippy::type_complexity,
+    clippy::module_inception,
     dead_code,
     non_camel_case_types
 )]
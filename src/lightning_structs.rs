@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_aux::prelude::*;
+use std::collections::HashMap;
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SendCoinsRequest {
@@ -37,9 +38,358 @@ pub struct SendCoinsRequest {
     /// Whether unconfirmed outputs should be used as inputs for the transaction.
     #[serde(deserialize_with = "deserialize_bool_from_anything")]
     pub spend_unconfirmed: bool,
+    /// A list of selected outpoints as inputs for the transaction.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outpoints: Vec<OutPoint>,
 }
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SendCoinsResponse {
     /// The transaction ID of the transaction
     pub txid: String,
 }
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalletBalanceResponse {
+    /// The balance of the wallet
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub total_balance: i64,
+    /// The confirmed balance of a wallet(with >= 1 confirmations)
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub confirmed_balance: i64,
+    /// The unconfirmed balance of a wallet(with 0 confirmations)
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub unconfirmed_balance: i64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Chain {
+    /// The blockchain the node is on (eg bitcoin, litecoin)
+    pub chain: String,
+    /// The network the node is on (eg regtest, testnet, mainnet)
+    pub network: String,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GetInfoResponse {
+    /// The identity pubkey of the current node.
+    pub identity_pubkey: String,
+    /// If applicable, the alias of the current node, e.g. "bob"
+    pub alias: String,
+    /// The node's current view of the height of the best block
+    pub block_height: u32,
+    /// Whether the wallet's view is synced to the main chain
+    pub synced_to_chain: bool,
+    /// A list of active chains the node is connected to
+    pub chains: Vec<Chain>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transaction {
+    /// The transaction hash
+    pub tx_hash: String,
+    /// The transaction amount, denominated in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub amount: i64,
+    /// The number of confirmations
+    pub num_confirmations: i32,
+    /// The hash of the block this transaction was included in
+    pub block_hash: String,
+    /// The height of the block this transaction was included in
+    pub block_height: i32,
+    /// Fees paid for this transaction
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub total_fees: i64,
+    /// An optional label that was set on transaction broadcast
+    pub label: String,
+    /// Outputs of the transaction
+    pub output_details: Vec<OutputDetail>,
+    /// Outpoints spent by the transaction
+    pub previous_outpoints: Vec<PreviousOutPoint>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputDetail {
+    /// The address of the output
+    pub address: String,
+    /// The index of the output in the transaction
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub output_index: u32,
+    /// The value of the output in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub amount: i64,
+    /// Whether the address belongs to the wallet
+    pub is_our_address: bool,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviousOutPoint {
+    /// The outpoint in format txid:n
+    pub outpoint: String,
+    /// Whether the spent output belongs to the wallet
+    pub is_our_output: bool,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransactionDetails {
+    /// The list of transactions relevant to the wallet
+    pub transactions: Vec<Transaction>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorResponse {
+    /// The gRPC status code
+    pub code: i32,
+    /// The error message
+    pub message: String,
+    /// Additional error details
+    pub details: Vec<serde_json::Value>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PayReq {
+    /// The node the invoice pays to
+    pub destination: String,
+    /// The hash the preimage of which settles the payment
+    pub payment_hash: String,
+    /// The amount in satoshis, zero if the payer picks it
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub num_satoshis: i64,
+    /// Unix time the invoice was created at
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timestamp: i64,
+    /// Seconds after timestamp the invoice expires
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub expiry: i64,
+    /// The description of the invoice
+    pub description: String,
+    /// The amount in millisatoshis, zero if the payer picks it
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub num_msat: i64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SendPaymentRequest {
+    /// A bare-bones invoice for a payment within the Lightning Network
+    pub payment_request: String,
+    /// Number of satoshis to send, only for invoices without an amount
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub amt: i64,
+    /// An upper limit on the amount of time we should spend when attempting to
+    /// fulfill the payment
+    pub timeout_seconds: i32,
+    /// The maximum number of satoshis that will be paid as a fee of the payment
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fee_limit_sat: i64,
+    /// If set, only the final payment update is streamed back
+    #[serde(deserialize_with = "deserialize_bool_from_anything")]
+    pub no_inflight_updates: bool,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Payment {
+    /// The payment hash
+    pub payment_hash: String,
+    /// The payment preimage
+    pub payment_preimage: String,
+    /// The value of the payment in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub value_sat: i64,
+    /// The fee paid for this payment in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fee_sat: i64,
+    /// The status of the payment (IN_FLIGHT, SUCCEEDED, FAILED)
+    pub status: String,
+    /// The failure reason if the payment failed
+    pub failure_reason: String,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaymentUpdate {
+    /// One update of a streamed payment
+    pub result: Option<Payment>,
+    /// Set instead of result when the stream failed
+    pub error: Option<ErrorResponse>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LightningAddress {
+    /// The identity pubkey of the Lightning node
+    pub pubkey: String,
+    /// The network location of the lightning node, e.g. `69.69.69.69:1337` or
+    /// `localhost:10011`
+    pub host: String,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ConnectPeerRequest {
+    /// Lightning address of the peer, in the format `<pubkey>@host`
+    pub addr: LightningAddress,
+    /// If set, the daemon will attempt to persistently connect to the target
+    /// peer
+    pub perm: bool,
+    /// The connection timeout value (in seconds) for this request
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout: u64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OpenChannelRequest {
+    /// The pubkey of the node to open a channel with, base64 encoded bytes
+    pub node_pubkey: String,
+    /// The number of satoshis the wallet should commit to the channel
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub local_funding_amount: i64,
+    /// The number of satoshis to push to the remote side as part of the initial
+    /// commitment state
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub push_sat: i64,
+    /// The target number of blocks that the funding transaction should be
+    /// confirmed by.
+    pub target_conf: i32,
+    /// A manual fee rate set in sat/vbyte that should be used when crafting the
+    /// funding transaction.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sat_per_vbyte: u64,
+    /// Whether this channel should be private, not announced to the greater
+    /// network.
+    pub private: bool,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelPoint {
+    /// Txid of the funding transaction, base64 encoded bytes in reverse order
+    pub funding_txid_bytes: String,
+    /// Hex-encoded string representing the funding transaction
+    pub funding_txid_str: String,
+    /// The index of the output of the funding transaction
+    pub output_index: u32,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Channel {
+    /// Whether this channel is active or not
+    pub active: bool,
+    /// The identity pubkey of the remote node
+    pub remote_pubkey: String,
+    /// The outpoint (txid:index) of the funding transaction
+    pub channel_point: String,
+    /// The unique channel ID for the channel, its upper 3 bytes are the funding
+    /// block height
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub chan_id: u64,
+    /// The total amount of funds held in this channel
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub capacity: i64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListChannelsResponse {
+    /// The list of active channels
+    pub channels: Vec<Channel>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PendingChannel {
+    /// The identity pubkey of the remote node
+    pub remote_node_pub: String,
+    /// The outpoint (txid:index) of the funding transaction
+    pub channel_point: String,
+    /// The total amount of funds held in this channel
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub capacity: i64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PendingOpenChannel {
+    /// The pending channel
+    pub channel: PendingChannel,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PendingChannelsResponse {
+    /// Channels pending opening
+    pub pending_open_channels: Vec<PendingOpenChannel>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EstimateFeeResponse {
+    /// The total fee in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fee_sat: i64,
+    /// The fee rate in satoshi/vbyte
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sat_per_vbyte: u64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SendManyRequest {
+    /// The map from addresses to amounts in satoshis for the transaction
+    #[serde(rename = "AddrToAmount")]
+    pub addr_to_amount: HashMap<String, i64>,
+    /// The target number of blocks that this transaction should be confirmed
+    /// by.
+    pub target_conf: i32,
+    /// A manual fee rate set in sat/vbyte that should be used when crafting the
+    /// transaction.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sat_per_vbyte: u64,
+    /// An optional label for the transaction, limited to 500 characters.
+    pub label: String,
+    /// The minimum number of confirmations each one of your outputs used for
+    /// the transaction must satisfy.
+    pub min_confs: i32,
+    /// Whether unconfirmed outputs should be used as inputs for the transaction.
+    pub spend_unconfirmed: bool,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SendManyResponse {
+    /// The id of the transaction
+    pub txid: String,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutPoint {
+    /// Reversed, hex-encoded string representing the transaction id
+    pub txid_str: String,
+    /// The index of the output on the transaction
+    pub output_index: u32,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Utxo {
+    /// The address
+    pub address: String,
+    /// The value of the unspent coin in satoshis
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub amount_sat: i64,
+    /// The outpoint in format txid:n
+    pub outpoint: OutPoint,
+    /// The number of confirmations for the Utxo
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub confirmations: i64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListUnspentResponse {
+    /// A list of utxos
+    pub utxos: Vec<Utxo>,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewAddressResponse {
+    /// The newly generated wallet address
+    pub address: String,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BumpFeeRequest {
+    /// The output to spend in a child transaction
+    pub outpoint: OutPoint,
+    /// The number of blocks the bumped transaction should confirm within
+    pub target_conf: u32,
+    /// Broadcast right away instead of waiting for the next block
+    pub immediate: bool,
+    /// The most the sweep may spend on fees, in satoshis
+    pub budget: u64,
+}
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BumpFeeResponse {
+    /// The status of the bump request
+    pub status: String,
+}
@@ -0,0 +1,1087 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ethers::types::U256;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serenity::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::backend::{Bumped, ClaimTag, Confirmation, Endpoint, PaymentBackend, Sent};
+use crate::cooldown::now;
+use crate::errors::Error;
+use crate::guard::check_lnd_network;
+use crate::lightning_structs;
+use crate::structs::{Coin, ConfigLnd};
+use crate::utils::format_units;
+
+/// REST client for one LND node, shared by the coins it pays out
+pub struct LndNode {
+    pub name: String,
+    pub config: ConfigLnd,
+    allow_mainnet: bool,
+    client: reqwest::Client,
+    // payouts waiting for the next SendMany, only used with a batch_window
+    batch: Mutex<Vec<Queued>>,
+    // transactions with a fee bump, batched payouts share theirs
+    bumped: Mutex<HashSet<String>>,
+}
+
+/// An on-chain payout waiting for its batch to go out
+struct Queued {
+    address: String,
+    amount: i64,
+    claim: ClaimTag,
+    done: oneshot::Sender<Result<Paid, Error>>,
+}
+
+/// Txid of an on-chain payout and its share of the fee
+type Paid = (String, Option<U256>);
+
+// gRPC status codes LND answers with
+const PERMISSION_DENIED: i32 = 7;
+const UNAUTHENTICATED: i32 = 16;
+
+// LND reports most failures as code 2 (unknown), only the message tells them apart
+const BAD_MACAROON: &[&str] = &[
+    "verification failed",
+    "cannot get macaroon",
+    "permission denied",
+];
+const INSUFFICIENT_FUNDS: &[&str] = &[
+    "insufficient funds",
+    "insufficient balance",
+    "not enough witness outputs",
+];
+const INVALID_ADDRESS: &[&str] = &[
+    "decoded address is of unknown format",
+    "is not intended for use on",
+    "not valid for this network",
+    "unknown address type",
+    "invalid bech32",
+    "checksum",
+    "invoice not for current active network",
+];
+
+/// Turns the body of a failed REST call (`code`, `message`, `details`) into a typed error
+fn error_from_response(status: u16, body: &str) -> Error {
+    match serde_json::from_str::<lightning_structs::ErrorResponse>(body) {
+        Ok(response) if !response.message.is_empty() => typed_error(response),
+        _ => Error::Lnd(format!("status {}: {}", status, body)),
+    }
+}
+
+fn typed_error(response: lightning_structs::ErrorResponse) -> Error {
+    let message = response.message.to_lowercase();
+    let mentions = |phrases: &[&str]| phrases.iter().any(|phrase| message.contains(phrase));
+
+    if response.code == PERMISSION_DENIED
+        || response.code == UNAUTHENTICATED
+        || mentions(BAD_MACAROON)
+    {
+        Error::LndUnauthorized(response.message)
+    } else if mentions(INSUFFICIENT_FUNDS) {
+        Error::NoFunds
+    } else if mentions(INVALID_ADDRESS) {
+        Error::InvalidAddress
+    } else {
+        Error::LndRpc {
+            code: response.code,
+            message: response.message,
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(response_message: String) -> Result<T, Error> {
+    match serde_json::from_str::<T>(&response_message) {
+        Ok(message) => Ok(message),
+        Err(_) => Err(Error::Lnd(response_message)),
+    }
+}
+
+impl LndNode {
+    /// Trusts only the node's own `tls.cert`, unless `insecure_tls` is explicitly set
+    pub fn new(name: String, config: ConfigLnd, allow_mainnet: bool) -> Result<Self, Error> {
+        // LND refuses every send that sets both halves of either pair
+        if config.target_conf != 0 && config.sat_per_vbyte != 0 {
+            return Err(Error::Lnd(format!(
+                "{} sets both target_conf and sat_per_vbyte, pick one",
+                name
+            )));
+        }
+        if config.min_confs != 0 && config.spend_unconfirmed {
+            return Err(Error::Lnd(format!(
+                "{} sets both min_confs and spend_unconfirmed, pick one",
+                name
+            )));
+        }
+
+        let macaroon = std::fs::read(&config.macaroon_path).map_err(|error| {
+            Error::Lnd(format!("couldn't read {}: {}", config.macaroon_path, error))
+        })?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Grpc-Metadata-macaroon",
+            hex::encode(macaroon)
+                .parse()
+                .map_err(|_| Error::Lnd(format!("invalid macaroon for {}", name)))?,
+        );
+
+        let builder = reqwest::Client::builder().default_headers(headers);
+        let builder = match &config.tls_cert_path {
+            Some(path) => {
+                let pem = std::fs::read(path)
+                    .map_err(|error| Error::Lnd(format!("couldn't read {}: {}", path, error)))?;
+                builder
+                    .tls_built_in_root_certs(false)
+                    .add_root_certificate(reqwest::Certificate::from_pem(&pem)?)
+            }
+            None if config.insecure_tls => {
+                println!(
+                    "WARNING: not verifying the certificate of lnd {}, the macaroon can be intercepted!",
+                    name
+                );
+                builder.danger_accept_invalid_certs(true)
+            }
+            None => {
+                return Err(Error::Lnd(format!(
+                    "no tls cert for {}, set its tls_cert_path or opt into insecure_tls",
+                    name
+                )));
+            }
+        };
+        let client = builder.build()?;
+
+        Ok(LndNode {
+            name,
+            config,
+            allow_mainnet,
+            client,
+            batch: Mutex::new(Vec::new()),
+            bumped: Mutex::new(HashSet::new()),
+        })
+    }
+
+    async fn call(&self, request: reqwest::RequestBuilder) -> Result<String, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(error_from_response(status.as_u16(), &body));
+        }
+
+        Ok(body)
+    }
+
+    async fn get(&self, endpoint: &str) -> Result<String, Error> {
+        let url = self.config.url.to_owned() + endpoint;
+        self.call(self.client.get(&url)).await
+    }
+
+    async fn post<T: Serialize>(&self, endpoint: &str, body: &T) -> Result<String, Error> {
+        let url = self.config.url.to_owned() + endpoint;
+        let body = serde_json::to_string(body)?;
+        self.call(self.client.post(&url).body(body)).await
+    }
+
+    pub async fn get_info(&self) -> Result<lightning_structs::GetInfoResponse, Error> {
+        parse(self.get("/v1/getinfo").await?)
+    }
+
+    /// Asks the node which network it is on, every time before it spends anything
+    pub async fn ensure_testnet(&self) -> Result<(), Error> {
+        let info = self.get_info().await?;
+        check_lnd_network(&info, self.allow_mainnet)
+    }
+
+    /// On-chain wallet transactions from `start_height` on, unconfirmed ones included
+    pub async fn get_transactions(
+        &self,
+        start_height: u32,
+    ) -> Result<lightning_structs::TransactionDetails, Error> {
+        parse(
+            self.get(&format!(
+                "/v1/transactions?start_height={}&end_height=-1",
+                start_height
+            ))
+            .await?,
+        )
+    }
+
+    /// Looks up one of the wallet's recent transactions, unconfirmed ones included
+    pub async fn find_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<Option<lightning_structs::Transaction>, Error> {
+        let info = self.get_info().await?;
+        let start_height = info.block_height.saturating_sub(TRACK_WINDOW);
+
+        let details = self.get_transactions(start_height).await?;
+
+        Ok(details
+            .transactions
+            .into_iter()
+            .find(|tx| tx.tx_hash == txid))
+    }
+
+    /// What LND would charge to pay `outputs` with the node's fee policy
+    pub async fn estimate_fee(
+        &self,
+        outputs: &HashMap<String, i64>,
+    ) -> Result<lightning_structs::EstimateFeeResponse, Error> {
+        let config = &self.config;
+        let mut query: Vec<String> = outputs
+            .iter()
+            .map(|(address, amount)| format!("AddrToAmount[{}]={}", address, amount))
+            .collect();
+        query.push(format!("target_conf={}", config.target_conf));
+        query.push(format!("min_confs={}", config.min_confs));
+        query.push(format!("spend_unconfirmed={}", config.spend_unconfirmed));
+
+        parse(
+            self.get(&format!("/v1/transactions/fee?{}", query.join("&")))
+                .await?,
+        )
+    }
+
+    /// Confirmed outputs of the on-chain wallet
+    pub async fn list_unspent(&self) -> Result<lightning_structs::ListUnspentResponse, Error> {
+        parse(
+            self.get("/v1/utxos?min_confs=1&max_confs=2147483647")
+                .await?,
+        )
+    }
+
+    pub async fn new_address(&self) -> Result<String, Error> {
+        let message: lightning_structs::NewAddressResponse =
+            parse(self.get("/v1/newaddress").await?)?;
+        Ok(message.address)
+    }
+
+    /// Has LND's sweeper spend the change of the pending `txid` in a child paying for both,
+    /// within the node's bump budget. Returns the child once the wallet has it,
+    /// None if there's no change to spend, the child didn't show up yet
+    /// or `txid` already got bumped through another payout of its batch.
+    pub async fn bump_fee(&self, txid: &str) -> Result<Option<String>, Error> {
+        if !self.bumped.lock().await.insert(txid.to_owned()) {
+            return Ok(None);
+        }
+
+        let result = self.request_bump(txid).await;
+        if !matches!(result, Ok(Some(_))) {
+            self.bumped.lock().await.remove(txid);
+        }
+        result
+    }
+
+    async fn request_bump(&self, txid: &str) -> Result<Option<String>, Error> {
+        let tx = match self.find_transaction(txid).await? {
+            Some(tx) if tx.num_confirmations == 0 => tx,
+            _ => return Ok(None),
+        };
+        let change = match tx
+            .output_details
+            .iter()
+            .find(|output| output.is_our_address)
+        {
+            Some(change) => change.output_index,
+            None => return Ok(None),
+        };
+
+        self.ensure_testnet().await?;
+
+        let request = lightning_structs::BumpFeeRequest {
+            outpoint: lightning_structs::OutPoint {
+                txid_str: txid.to_owned(),
+                output_index: change,
+            },
+            target_conf: BUMP_TARGET_CONF,
+            immediate: true,
+            budget: self.config.bump_budget_sat,
+        };
+        let _: lightning_structs::BumpFeeResponse =
+            parse(self.post("/v2/wallet/bumpfee", &request).await?)?;
+
+        // the sweeper broadcasts in the background
+        let outpoint = format!("{}:{}", txid, change);
+        for _ in 0..CHILD_POLLS {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+
+            let height = self.get_info().await?.block_height;
+            let details = self.get_transactions(height).await?;
+            if let Some(child) = find_spender(&details.transactions, &outpoint) {
+                return Ok(Some(child.tx_hash.to_owned()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn decode_pay_req(&self, pay_req: &str) -> Result<lightning_structs::PayReq, Error> {
+        parse(self.get(&format!("/v1/payreq/{}", pay_req)).await?)
+    }
+
+    /// Pays an invoice and waits for the outcome, the REST stream carries one json update per line
+    pub async fn send_payment(
+        &self,
+        request: &lightning_structs::SendPaymentRequest,
+    ) -> Result<lightning_structs::Payment, Error> {
+        self.ensure_testnet().await?;
+
+        let body = self.post("/v2/router/send", request).await?;
+
+        let mut last = None;
+        for line in body.lines().filter(|line| !line.trim().is_empty()) {
+            let update: lightning_structs::PaymentUpdate = parse(line.to_owned())?;
+            if let Some(error) = update.error {
+                return Err(typed_error(error));
+            }
+            last = update.result;
+        }
+
+        let payment = last.ok_or_else(|| Error::Lnd(String::from("empty payment stream")))?;
+        match payment.status.as_str() {
+            "SUCCEEDED" => Ok(payment),
+            "FAILED" if payment.failure_reason == "FAILURE_REASON_INSUFFICIENT_BALANCE" => {
+                Err(Error::NoFunds)
+            }
+            _ => Err(Error::PaymentFailed(format!(
+                "{} {}",
+                payment.status, payment.failure_reason
+            ))),
+        }
+    }
+
+    /// Connects to a peer, being connected already is fine
+    pub async fn connect_peer(&self, pubkey: &str, host: &str) -> Result<(), Error> {
+        let request = lightning_structs::ConnectPeerRequest {
+            addr: lightning_structs::LightningAddress {
+                pubkey: pubkey.to_owned(),
+                host: host.to_owned(),
+            },
+            perm: false,
+            timeout: 30,
+        };
+
+        match self.post("/v1/peers", &request).await {
+            Ok(_) => Ok(()),
+            Err(Error::LndRpc { message, .. }) if message.contains("already connected") => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Opens a channel and returns its channel point (txid:index) once the funding tx is out
+    pub async fn open_channel(
+        &self,
+        request: &lightning_structs::OpenChannelRequest,
+    ) -> Result<String, Error> {
+        self.ensure_testnet().await?;
+
+        let point: lightning_structs::ChannelPoint =
+            parse(self.post("/v1/channels", request).await?)?;
+
+        let txid = if point.funding_txid_str.is_empty() {
+            // the raw bytes come in little endian, txids are shown the other way round
+            let mut bytes = STANDARD
+                .decode(&point.funding_txid_bytes)
+                .map_err(|error| Error::Lnd(format!("bad funding txid: {}", error)))?;
+            bytes.reverse();
+            hex::encode(bytes)
+        } else {
+            point.funding_txid_str
+        };
+
+        Ok(format!("{}:{}", txid, point.output_index))
+    }
+
+    pub async fn list_channels(&self) -> Result<lightning_structs::ListChannelsResponse, Error> {
+        parse(self.get("/v1/channels").await?)
+    }
+
+    pub async fn pending_channels(
+        &self,
+    ) -> Result<lightning_structs::PendingChannelsResponse, Error> {
+        parse(self.get("/v1/channels/pending").await?)
+    }
+
+    pub async fn wallet_balance(&self) -> Result<lightning_structs::WalletBalanceResponse, Error> {
+        parse(self.get("/v1/balance/blockchain").await?)
+    }
+
+    pub async fn send_coins(
+        &self,
+        request: &lightning_structs::SendCoinsRequest,
+    ) -> Result<lightning_structs::SendCoinsResponse, Error> {
+        self.ensure_testnet().await?;
+
+        parse(self.post("/v1/transactions", request).await?)
+    }
+
+    pub async fn send_many(
+        &self,
+        request: &lightning_structs::SendManyRequest,
+    ) -> Result<lightning_structs::SendManyResponse, Error> {
+        self.ensure_testnet().await?;
+
+        parse(self.post("/v1/transactions/many", request).await?)
+    }
+
+    /// Pays all `outputs` in one transaction following the node's fee policy
+    pub async fn pay(&self, outputs: &HashMap<String, i64>, label: &str) -> Result<Paid, Error> {
+        let config = &self.config;
+        if let Some(cap) = config.max_sat_per_vbyte {
+            let rate = match config.sat_per_vbyte {
+                0 => self.estimate_fee(outputs).await?.sat_per_vbyte,
+                rate => rate,
+            };
+            if rate > cap {
+                return Err(Error::FeeTooHigh { rate, cap });
+            }
+        }
+
+        let txid = match outputs.iter().next() {
+            Some((address, amount)) if outputs.len() == 1 => {
+                let request = lightning_structs::SendCoinsRequest {
+                    addr: address.to_owned(),
+                    amount: *amount,
+                    target_conf: config.target_conf,
+                    sat_per_vbyte: config.sat_per_vbyte,
+                    min_confs: config.min_confs,
+                    spend_unconfirmed: config.spend_unconfirmed,
+                    label: label.to_owned(),
+                    ..Default::default()
+                };
+                self.send_coins(&request).await?.txid
+            }
+            _ => {
+                let request = lightning_structs::SendManyRequest {
+                    addr_to_amount: outputs.clone(),
+                    target_conf: config.target_conf,
+                    sat_per_vbyte: config.sat_per_vbyte,
+                    min_confs: config.min_confs,
+                    spend_unconfirmed: config.spend_unconfirmed,
+                    label: label.to_owned(),
+                };
+                self.send_many(&request).await?.txid
+            }
+        };
+
+        // the fee is only known once LND has the transaction in its wallet
+        let fee = match self.find_transaction(&txid).await {
+            Ok(tx) => tx.map(|tx| U256::from(tx.total_fees.max(0))),
+            Err(error) => {
+                println!("Couldn't look up the fee of {}: {}", txid, error);
+                None
+            }
+        };
+
+        Ok((txid, fee))
+    }
+
+    /// Adds a payout to the node's next batch and waits until the batch went out.
+    /// The first payout of a batch starts its window.
+    pub async fn queue(
+        self: &Arc<Self>,
+        address: &str,
+        amount: i64,
+        window: u64,
+        claim: &ClaimTag,
+    ) -> Result<Paid, Error> {
+        let (done, paid) = oneshot::channel();
+
+        {
+            let mut batch = self.batch.lock().await;
+            if batch.is_empty() {
+                let node = self.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(window)).await;
+                    node.flush().await;
+                });
+            }
+            batch.push(Queued {
+                address: address.to_owned(),
+                amount,
+                claim: claim.clone(),
+                done,
+            });
+        }
+
+        paid.await
+            .map_err(|_| Error::Lnd(String::from("batch went away")))?
+    }
+
+    async fn flush(&self) {
+        let queued = std::mem::take(&mut *self.batch.lock().await);
+        if queued.is_empty() {
+            return;
+        }
+
+        let mut outputs: HashMap<String, i64> = HashMap::new();
+        for payout in queued.iter() {
+            *outputs.entry(payout.address.to_owned()).or_default() += payout.amount;
+        }
+
+        let claims: Vec<ClaimTag> = queued.iter().map(|payout| payout.claim.clone()).collect();
+
+        match self.pay(&outputs, &claim_label(&claims)).await {
+            Ok((txid, fee)) => {
+                println!(
+                    "Batched {} payouts of lnd {} in {}",
+                    queued.len(),
+                    self.name,
+                    txid
+                );
+                let share = fee.map(|fee| fee / U256::from(queued.len()));
+                for payout in queued {
+                    let _ = payout.done.send(Ok((txid.to_owned(), share)));
+                }
+            }
+            Err(Error::InvalidAddress) if outputs.len() > 1 => {
+                // one bad address fails the whole batch, give everyone else their own send
+                println!(
+                    "Batch of lnd {} has an invalid address, sending one by one",
+                    self.name
+                );
+                for payout in queued {
+                    let outputs = HashMap::from([(payout.address, payout.amount)]);
+                    let label = claim_label(&[payout.claim]);
+                    let _ = payout.done.send(self.pay(&outputs, &label).await);
+                }
+            }
+            Err(error) => {
+                println!("Batch of lnd {} failed: {}", self.name, error);
+                for payout in queued {
+                    let _ = payout.done.send(Err(batch_error(&error)));
+                }
+            }
+        }
+    }
+}
+
+/// Errors can't be cloned, every payout of a failed batch gets its own copy of what matters
+fn batch_error(error: &Error) -> Error {
+    match error {
+        Error::NoFunds => Error::NoFunds,
+        Error::InvalidAddress => Error::InvalidAddress,
+        Error::FeeTooHigh { rate, cap } => Error::FeeTooHigh {
+            rate: *rate,
+            cap: *cap,
+        },
+        error => Error::Lnd(error.to_string()),
+    }
+}
+
+// LND refuses longer transaction labels
+const MAX_LABEL: usize = 500;
+const LABEL_PREFIX: &str = "faucet";
+
+/// "faucet COIN/claim/user ..." for as many of `claims` as fit in a label
+pub fn claim_label(claims: &[ClaimTag]) -> String {
+    let mut label = String::from(LABEL_PREFIX);
+    for (index, claim) in claims.iter().enumerate() {
+        let entry = format!(" {}/{}/{}", claim.coin, claim.id, claim.user);
+        if label.len() + entry.len() > MAX_LABEL {
+            println!("Label full, {} claims left out of it", claims.len() - index);
+            break;
+        }
+        label += &entry;
+    }
+    label
+}
+
+/// The claims a faucet label carries, None for labels the faucet didn't write
+pub fn parse_label(label: &str) -> Option<Vec<ClaimTag>> {
+    let mut words = label.split_whitespace();
+    if words.next() != Some(LABEL_PREFIX) {
+        return None;
+    }
+
+    words
+        .map(|entry| {
+            let mut parts = entry.split('/');
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(coin), Some(id), Some(user), None) => Some(ClaimTag {
+                    id: id.to_owned(),
+                    coin: coin.to_owned(),
+                    user: user.to_owned(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// how far back confirmations are looked up, far more than a payout is ever tracked
+const TRACK_WINDOW: u32 = 1000;
+// how long LND keeps looking for a route to a faucet invoice
+const PAYMENT_TIMEOUT: i64 = 60;
+// a bumped payout should make it into one of the next blocks
+const BUMP_TARGET_CONF: u32 = 2;
+// how often the wallet is checked for the child of a bump
+const CHILD_POLLS: u32 = 5;
+
+/// The transaction spending `outpoint` (txid:n), if the wallet has one
+fn find_spender<'a>(
+    transactions: &'a [lightning_structs::Transaction],
+    outpoint: &str,
+) -> Option<&'a lightning_structs::Transaction> {
+    transactions.iter().find(|tx| {
+        tx.previous_outpoints
+            .iter()
+            .any(|previous| previous.outpoint == outpoint)
+    })
+}
+
+/// Splits a node uri into its pubkey and host:port
+fn parse_node_uri(uri: &str) -> Result<(&str, &str), Error> {
+    let (pubkey, host) = uri.split_once('@').ok_or(Error::InvalidAddress)?;
+
+    let pubkey_bytes = hex::decode(pubkey).map_err(|_| Error::InvalidAddress)?;
+    if pubkey_bytes.len() != 33 || host.is_empty() {
+        return Err(Error::InvalidAddress);
+    }
+
+    Ok((pubkey, host))
+}
+
+// BOLT11 invoices start with "ln" + currency, no on-chain address format does,
+// wallets often hand them out behind a "lightning:" uri scheme
+fn as_invoice(address: &str) -> Option<&str> {
+    let invoice = match address.get(..10) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("lightning:") => &address[10..],
+        _ => address,
+    };
+
+    invoice
+        .get(..2)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("ln"))
+        .map(|_| invoice)
+}
+
+/// Checks a decoded invoice against the coin's amount, returns the sats to send along with it
+fn invoice_amount(
+    pay_req: &lightning_structs::PayReq,
+    coin: &Coin,
+    amount: i64,
+    now: i64,
+) -> Result<i64, Error> {
+    if pay_req.num_msat > amount.saturating_mul(1000) {
+        return Err(Error::InvoiceRejected(format!(
+            "it asks for more than {} {}",
+            format_units(coin.amount, coin.decimals),
+            coin.name
+        )));
+    }
+
+    // leave LND the whole timeout to find a route before the invoice runs out
+    let expires_at = pay_req.timestamp + pay_req.expiry;
+    if expires_at < now + PAYMENT_TIMEOUT {
+        return Err(Error::InvoiceRejected(String::from(
+            "it is expired or about to expire",
+        )));
+    }
+
+    // invoices without an amount get exactly the coin's
+    Ok(if pay_req.num_msat == 0 { amount } else { 0 })
+}
+
+pub struct LndBackend {
+    coin: Coin,
+    node: Arc<LndNode>,
+}
+
+impl LndBackend {
+    pub fn new(coin: Coin, node: Arc<LndNode>) -> Self {
+        LndBackend { coin, node }
+    }
+
+    /// Pays a BOLT11 invoice for up to the coin's amount, invoices without one get exactly that
+    async fn pay_invoice(&self, invoice: &str, amount: i64) -> Result<Sent, Error> {
+        let pay_req = self.node.decode_pay_req(invoice).await?;
+        let amt = invoice_amount(&pay_req, &self.coin, amount, now() as i64)?;
+
+        let request = lightning_structs::SendPaymentRequest {
+            payment_request: invoice.to_owned(),
+            amt,
+            timeout_seconds: PAYMENT_TIMEOUT as i32,
+            fee_limit_sat: self.node.config.max_routing_fee_sat,
+            no_inflight_updates: true,
+        };
+
+        let payment = self.node.send_payment(&request).await?;
+
+        Ok(Sent::Payment {
+            hash: payment.payment_hash,
+            preimage: payment.payment_preimage,
+            amount: U256::from(payment.value_sat.max(0)),
+        })
+    }
+}
+
+#[async_trait]
+impl PaymentBackend for LndBackend {
+    fn coin(&self) -> &Coin {
+        &self.coin
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::Lnd(self.node.name.to_owned())
+    }
+
+    async fn send(&self, address: &str, claim: &ClaimTag) -> Result<Sent, Error> {
+        let amount = u128::try_from(self.coin.amount)
+            .ok()
+            .and_then(|amount| i64::try_from(amount).ok())
+            .ok_or_else(|| Error::InvalidAmount(self.coin.amount.to_string()))?;
+
+        if let Some(invoice) = as_invoice(address) {
+            return self.pay_invoice(invoice, amount).await;
+        }
+
+        let (txid, fee) = match self.node.config.batch_window {
+            Some(window) => self.node.queue(address, amount, window, claim).await?,
+            None => {
+                let outputs = HashMap::from([(address.to_owned(), amount)]);
+                let label = claim_label(std::slice::from_ref(claim));
+                self.node.pay(&outputs, &label).await?
+            }
+        };
+
+        Ok(Sent::Transaction { txid, fee })
+    }
+
+    async fn confirmation(&self, txid: &str) -> Result<Confirmation, Error> {
+        Ok(match self.node.find_transaction(txid).await? {
+            Some(tx) if tx.num_confirmations > 0 => {
+                Confirmation::Confirmed(tx.block_height.max(0) as u64)
+            }
+            Some(_) => Confirmation::Pending,
+            None => Confirmation::Unknown,
+        })
+    }
+
+    fn bump_after(&self) -> Option<Duration> {
+        let minutes = self.node.config.bump_after?;
+        Some(Duration::from_secs(minutes * 60))
+    }
+
+    async fn bump(&self, txid: &str) -> Result<Option<Bumped>, Error> {
+        let child = self.node.bump_fee(txid).await?;
+        Ok(child.map(|txid| Bumped {
+            txid,
+            replaces: false,
+        }))
+    }
+
+    async fn balance(&self) -> Result<U256, Error> {
+        let message = self.node.wallet_balance().await?;
+
+        Ok(U256::from(message.confirmed_balance.max(0)))
+    }
+
+    fn channel_capacity(&self) -> Option<U256> {
+        let capacity = self.node.config.channel_capacity?;
+        Some(U256::from(capacity.max(0)))
+    }
+
+    async fn open_channel(&self, uri: &str) -> Result<String, Error> {
+        let capacity = self
+            .node
+            .config
+            .channel_capacity
+            .ok_or_else(|| Error::ChannelsUnsupported(self.coin.name.to_owned()))?;
+        let (pubkey, host) = parse_node_uri(uri)?;
+
+        self.node.connect_peer(pubkey, host).await?;
+
+        let request = lightning_structs::OpenChannelRequest {
+            node_pubkey: STANDARD.encode(hex::decode(pubkey)?),
+            local_funding_amount: capacity,
+            push_sat: self.node.config.channel_push_sat,
+            target_conf: self.node.config.target_conf,
+            sat_per_vbyte: self.node.config.sat_per_vbyte,
+            private: false,
+        };
+
+        self.node.open_channel(&request).await
+    }
+
+    async fn channel_status(&self, channel_point: &str) -> Result<Confirmation, Error> {
+        let channels = self.node.list_channels().await?;
+        if let Some(channel) = channels
+            .channels
+            .iter()
+            .find(|channel| channel.channel_point == channel_point)
+        {
+            if !channel.active {
+                return Ok(Confirmation::Pending);
+            }
+            // the short channel id starts with the funding block height
+            return Ok(Confirmation::Confirmed(channel.chan_id >> 40));
+        }
+
+        let pending = self.node.pending_channels().await?;
+        if pending
+            .pending_open_channels
+            .iter()
+            .any(|pending| pending.channel.channel_point == channel_point)
+        {
+            return Ok(Confirmation::Pending);
+        }
+
+        Ok(Confirmation::Unknown)
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), Error> {
+        // LND knows the network's address formats, only weed out obvious garbage here
+        let address = as_invoice(address).unwrap_or(address);
+        if address.is_empty() || !address.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::InvalidAddress);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insufficient_funds() {
+        let body = include_str!("../fixtures/lnd/insufficient_funds.json");
+        assert!(matches!(error_from_response(500, body), Error::NoFunds));
+    }
+
+    #[test]
+    fn unknown_address_format() {
+        let body = include_str!("../fixtures/lnd/unknown_address_format.json");
+        assert!(matches!(
+            error_from_response(500, body),
+            Error::InvalidAddress
+        ));
+    }
+
+    #[test]
+    fn address_for_other_network() {
+        let body = include_str!("../fixtures/lnd/wrong_network_address.json");
+        assert!(matches!(
+            error_from_response(500, body),
+            Error::InvalidAddress
+        ));
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let body = include_str!("../fixtures/lnd/bad_checksum.json");
+        assert!(matches!(
+            error_from_response(500, body),
+            Error::InvalidAddress
+        ));
+    }
+
+    #[test]
+    fn mentioning_address_is_not_an_invalid_address() {
+        let body = include_str!("../fixtures/lnd/wallet_locked.json");
+        match error_from_response(500, body) {
+            Error::LndRpc { code, message } => {
+                assert_eq!(code, 2);
+                assert!(message.contains("wallet locked"));
+            }
+            error => panic!("unexpected {:?}", error),
+        }
+    }
+
+    #[test]
+    fn invalid_macaroon() {
+        let body = include_str!("../fixtures/lnd/invalid_macaroon.json");
+        assert!(matches!(
+            error_from_response(500, body),
+            Error::LndUnauthorized(_)
+        ));
+    }
+
+    #[test]
+    fn permission_denied_code() {
+        let body = include_str!("../fixtures/lnd/permission_denied.json");
+        assert!(matches!(
+            error_from_response(403, body),
+            Error::LndUnauthorized(_)
+        ));
+    }
+
+    #[test]
+    fn non_json_body() {
+        match error_from_response(502, "Bad Gateway") {
+            Error::Lnd(message) => assert_eq!(message, "status 502: Bad Gateway"),
+            error => panic!("unexpected {:?}", error),
+        }
+    }
+
+    #[test]
+    fn finds_bump_child() {
+        let body = include_str!("../fixtures/lnd/bumped_transactions.json");
+        let details: lightning_structs::TransactionDetails = parse(body.to_owned()).unwrap();
+
+        let parent = "4f3cbd4cf4c3f0e1e2dcc5e9d6e8a1f2b0c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7";
+        let child = find_spender(&details.transactions, &format!("{}:1", parent)).unwrap();
+        assert_eq!(
+            child.tx_hash,
+            "9a8b7c6d5e4f30211203f4e5d6c7b8a99a8b7c6d5e4f30211203f4e5d6c7b8a9"
+        );
+        assert!(find_spender(&details.transactions, &format!("{}:0", parent)).is_none());
+    }
+
+    #[test]
+    fn claim_labels_round_trip() {
+        let claims = vec![
+            ClaimTag::new(123456789012345678, "LTC", 1700000000, "secret"),
+            ClaimTag::new(876543210987654321, "LTC", 1700000042, "secret"),
+        ];
+        let label = claim_label(&claims);
+        assert!(label.starts_with("faucet LTC/"));
+
+        let parsed = parse_label(&label).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].id, claims[1].id);
+        assert_eq!(parsed[1].user, claims[1].user);
+
+        assert!(parse_label("openchannel:shortchanid-123").is_none());
+        assert!(parse_label("faucet fan-out").is_none());
+    }
+
+    #[test]
+    fn claim_labels_stay_within_limit() {
+        let claims: Vec<ClaimTag> = (0..100)
+            .map(|user_id| ClaimTag::new(user_id, "BTC", 1700000000, "secret"))
+            .collect();
+        let label = claim_label(&claims);
+        assert!(label.len() <= MAX_LABEL);
+        assert!(parse_label(&label).unwrap().len() < claims.len());
+    }
+
+    const INVOICE: &str = "lntb10u1pjexample";
+    const NODE_PUBKEY: &str = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
+
+    fn btc() -> Coin {
+        Coin {
+            name: String::from("BTC"),
+            amount: U256::from(10000),
+            network: String::from("lightning"),
+            kind: crate::structs::CoinKind::Native,
+            contract: String::new(),
+            decimals: 8,
+            explorer_tx: None,
+            explorer_address: None,
+        }
+    }
+
+    fn pay_req(num_msat: i64, timestamp: i64) -> lightning_structs::PayReq {
+        lightning_structs::PayReq {
+            num_satoshis: num_msat / 1000,
+            num_msat,
+            timestamp,
+            expiry: 3600,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tells_invoices_from_addresses() {
+        assert_eq!(as_invoice(INVOICE), Some(INVOICE));
+        assert_eq!(as_invoice("LNTB10U1PJEXAMPLE"), Some("LNTB10U1PJEXAMPLE"));
+        assert_eq!(
+            as_invoice("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            None
+        );
+        assert_eq!(as_invoice("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"), None);
+        assert_eq!(as_invoice(""), None);
+    }
+
+    #[test]
+    fn strips_lightning_scheme() {
+        let uri = format!("lightning:{}", INVOICE);
+        assert_eq!(as_invoice(&uri), Some(INVOICE));
+        let uri = format!("LIGHTNING:{}", INVOICE);
+        assert_eq!(as_invoice(&uri), Some(INVOICE));
+        assert_eq!(
+            as_invoice("lightning:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_node_uri() {
+        let uri = format!("{}@127.0.0.1:9735", NODE_PUBKEY);
+        assert_eq!(
+            parse_node_uri(&uri).unwrap(),
+            (NODE_PUBKEY, "127.0.0.1:9735")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_node_uri() {
+        let uris = [
+            NODE_PUBKEY.to_owned(),
+            format!("{}@", NODE_PUBKEY),
+            format!("{}@127.0.0.1:9735", &NODE_PUBKEY[..64]),
+            format!("{}zz@127.0.0.1:9735", &NODE_PUBKEY[..64]),
+            String::from("@127.0.0.1:9735"),
+        ];
+        for uri in uris {
+            assert!(
+                matches!(parse_node_uri(&uri), Err(Error::InvalidAddress)),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn zero_amount_invoice_gets_the_coin_amount() {
+        let now = 1700000000;
+        assert_eq!(
+            invoice_amount(&pay_req(0, now), &btc(), 10000, now).unwrap(),
+            10000
+        );
+        // invoices with an amount are paid as they are
+        assert_eq!(
+            invoice_amount(&pay_req(5000000, now), &btc(), 10000, now).unwrap(),
+            0
+        );
+        assert!(matches!(
+            invoice_amount(&pay_req(10000001, now), &btc(), 10000, now),
+            Err(Error::InvoiceRejected(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_expired_invoice() {
+        let now = 1700000000;
+        assert!(matches!(
+            invoice_amount(&pay_req(0, now - 3600), &btc(), 10000, now),
+            Err(Error::InvoiceRejected(_))
+        ));
+        // one that runs out before the payment timeout would too
+        assert!(matches!(
+            invoice_amount(
+                &pay_req(0, now - 3600 + PAYMENT_TIMEOUT / 2),
+                &btc(),
+                10000,
+                now
+            ),
+            Err(Error::InvoiceRejected(_))
+        ));
+        assert!(invoice_amount(
+            &pay_req(0, now - 3600 + PAYMENT_TIMEOUT),
+            &btc(),
+            10000,
+            now
+        )
+        .is_ok());
+    }
+}
@@ -1,185 +1,132 @@
+#![allow(clippy::result_large_err)] // errors carry the ethers middleware errors as-is
+
+mod backend;
+mod balance;
+mod claim;
+mod commands;
+mod cooldown;
 mod errors;
+mod evm;
+mod guard;
+mod health;
 mod ierc20;
 mod json_rpc;
 mod lightning_structs;
-mod send;
+mod lnd;
+mod maintenance;
+mod reconcile;
+mod storage;
 mod structs;
+mod tracker;
 mod utils;
 
+use backend::{build_backends, Backends};
 use config_file::FromConfigFile;
 use ethers::signers::coins_bip39::English;
-use ethers::signers::LocalWallet;
 use ethers::signers::MnemonicBuilder;
+use lnd::LndNode;
 use serenity::async_trait;
+use serenity::model::application::interaction::Interaction;
 use serenity::model::channel::Message;
 use serenity::model::gateway::Ready;
+use serenity::model::id::ChannelId;
 use serenity::prelude::*;
 use serenity::utils::MessageBuilder;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
-use structs::Coin;
-use structs::Network;
-use structs::{Cache, Config, Macaroon};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use storage::Storage;
+use structs::{Cache, Config};
+use tracker::Reply;
 
 struct Faucet {
     config: Config,
     cache: Mutex<Cache>,
-    macaroon: Macaroon,
-    eth_wallet: LocalWallet,
+    storage: Mutex<Storage>,
+    backends: Arc<Backends>,
+    lnd_nodes: Arc<HashMap<String, Arc<LndNode>>>,
+    started: AtomicBool,
 }
 
 #[async_trait]
 impl EventHandler for Faucet {
     async fn message(&self, context: Context, msg: Message) {
-        if !msg.author.bot {
-            let coin_address: Vec<&str> = msg.content.split('-').collect();
-
-            if coin_address.len() == 2 {
-                let coin_name = coin_address[0].replace(' ', "").to_uppercase(); // allow spaces and lowercase coin
-                let address = coin_address[1].replace(' ', "");
-
-                let response_msg: String;
-
-                if let Some(config_coin) = self.config.coins.get(&coin_name) {
-                    let coin = Coin {
-                        name: coin_name.to_owned(),
-                        amount: config_coin.amount,
-                        decimals: config_coin.decimals,
-                        contract: config_coin.contract.to_owned(),
-                        network: config_coin.network.to_owned(),
-                    };
-
-                    let mut cache = self.cache.lock().await;
-
-                    let coin_timestamp = match cache.get(&msg.author.id) {
-                        Some(user) => match user.get(&coin_name) {
-                            Some(timestamp) => timestamp.to_owned(),
-                            None => 0,
-                        },
-                        None => 0,
-                    };
-
-                    let current_timestamp = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .expect("Time went backwards")
-                        .as_secs();
-
-                    // check if enough hours had elapsed from last request
-                    if current_timestamp > coin_timestamp + self.config.limit * 3600 {
-                        let tx_res = match coin.network {
-                            Network::Lightning => self.lnd_send(&coin, &address, coin.amount).await,
-                            Network::Ethereum => match coin_name.as_str() {
-                                "ETH" => {
-                                    self.eth_send_transaction(&coin, &address, coin.amount)
-                                        .await
-                                }
-                                _ => {
-                                    self.erc20_send_transaction(&coin, &address, coin.amount)
-                                        .await
-                                }
-                            },
-                            Network::Arbitrum => match coin_name.as_str() {
-                                "AETH" => {
-                                    self.eth_send_transaction(&coin, &address, coin.amount)
-                                        .await
-                                }
-                                _ => {
-                                    self.erc20_send_transaction(&coin, &address, coin.amount)
-                                        .await
-                                }
-                            },
-                        };
-
-                        match tx_res {
-                            Ok(txid) => {
-                                match cache.get_mut(&msg.author.id) {
-                                    Some(user) => {
-                                        user.insert(coin_name.to_owned(), current_timestamp);
-                                    }
-                                    None => {
-                                        let mut user = HashMap::new();
-                                        user.insert(coin_name.to_owned(), current_timestamp);
-                                        cache.insert(msg.author.id, user);
-                                    }
-                                }
-
-                                let explorer = match coin.network {
-                                    Network::Lightning => match coin_name.as_str() {
-                                        "BTC" => format!(
-                                            "https://www.blockchain.com/btc-testnet/tx/{}",
-                                            txid
-                                        ),
-                                        "LTC" => format!(
-                                            "https://blockexplorer.one/litecoin/testnet/tx/{}",
-                                            txid
-                                        ),
-                                        _ => {
-                                            return;
-                                        }
-                                    },
-                                    Network::Ethereum => {
-                                        format!("https://goerli.etherscan.io/tx/{}", txid)
-                                    }
-                                    Network::Arbitrum => {
-                                        format!(
-                                            "https://goerli-rollup-explorer.arbitrum.io/tx/{}",
-                                            txid
-                                        )
-                                    }
-                                };
-
-                                response_msg =
-                                    format!("Sent {} {}! {}", coin.amount, coin_name, explorer);
-                            }
-                            Err(error) => {
-                                println!("{}", error);
-                                response_msg = match error {
-                                    errors::Error::InvalidAddress => {
-                                        String::from("Invalid address!")
-                                    }
-                                    errors::Error::NoFunds => String::from("Faucet out of funds!"),
-                                    _ => {
-                                        if error
-                                            .to_string()
-                                            .contains("replacement transaction underpriced")
-                                        {
-                                            String::from("Please wait for the previous transaction to be confirmed!")
-                                        } else {
-                                            String::from("Transaction failed, retry later!")
-                                        }
-                                    }
-                                };
-                            }
-                        }
-                    } else {
-                        let remaining =
-                            coin_timestamp + self.config.limit * 3600 - current_timestamp;
-                        let hours = remaining / 3600;
-                        let mins = (remaining - hours * 3600) / 60;
-                        response_msg = format!(
-                            "Please wait another {}h{}m before requesting new {}!",
-                            hours, mins, coin_name
-                        );
-                    }
-                } else {
-                    response_msg = "Coin not supported!".to_string();
-                }
+        if !self.config.text_commands || msg.author.bot {
+            return;
+        }
 
-                let response = MessageBuilder::new()
-                    .mention(&msg.author)
-                    .push(response_msg)
-                    .build();
+        // COIN-address, or COIN-channel-pubkey@host:port where the host may contain dashes
+        let parts: Vec<&str> = msg.content.splitn(3, '-').collect();
 
-                if let Err(why) = msg.channel_id.say(&context.http, &response).await {
-                    println!("Error sending message: {:?}", why);
+        let (response_msg, payout) = match parts.as_slice() {
+            [command] if command.trim().eq_ignore_ascii_case("balance") => {
+                (self.balances().await, None)
+            }
+            [coin, channel, uri] if channel.trim().eq_ignore_ascii_case("channel") => {
+                self.request_channel(msg.author.id, coin, uri).await
+            }
+            [coin, address] => self.claim(msg.author.id, coin, address).await,
+            _ => return,
+        };
+
+        let response = MessageBuilder::new()
+            .mention(&msg.author)
+            .push(response_msg)
+            .build();
+
+        match msg.channel_id.say(&context.http, &response).await {
+            Ok(sent) => {
+                if let Some(payout) = payout {
+                    let reply = Reply::Message(sent);
+                    self.track(&context.http, reply, response, payout).await;
                 }
             }
+            Err(why) => println!("Error sending message: {:?}", why),
+        }
+    }
+
+    async fn interaction_create(&self, context: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::ApplicationCommand(command) => self.run_command(&context, command).await,
+            Interaction::Autocomplete(autocomplete) => {
+                self.autocomplete(&context, autocomplete).await
+            }
+            _ => {}
         }
     }
 
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, context: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+
+        if let Err(why) = self.register_commands(&context).await {
+            println!("Error registering slash commands: {:?}", why);
+        }
+
+        // ready fires again after every reconnect, only start the background tasks once
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let admin_channel = self.config.admin_channel_id.map(ChannelId);
+        if let Some(channel_id) = admin_channel {
+            tokio::spawn(balance::monitor(
+                self.backends.clone(),
+                context.http.clone(),
+                channel_id,
+                self.config.low_balance_payouts,
+                Duration::from_secs(self.config.balance_interval * 60),
+            ));
+        }
+
+        if let Some(interval) = self.config.maintenance_interval {
+            tokio::spawn(maintenance::schedule(
+                self.lnd_nodes.clone(),
+                context.http.clone(),
+                admin_channel,
+                Duration::from_secs(interval * 3600),
+            ));
+        }
     }
 }
 
@@ -188,39 +135,45 @@ async fn main() {
     let config =
         Config::from_config_file("config.toml").expect("config.toml not properly formatted");
 
-    let btc_macaroon = match std::fs::read(PathBuf::from(&config.btc_macaroon_dir)) {
-        Ok(macaroon) => hex::encode(macaroon),
+    let eth_wallet = MnemonicBuilder::<English>::default()
+        .phrase(&*config.eth_mnemonic)
+        .build()
+        .unwrap();
+
+    let mut registry = match build_backends(&config, &eth_wallet).await {
+        Ok(registry) => registry,
         Err(error) => {
-            panic!("BTC admin.macaroon file not found!\nERROR: {:#?}", error);
+            panic!("Invalid coin configuration!\nERROR: {:#?}", error);
         }
     };
 
-    let ltc_macaroon = match std::fs::read(PathBuf::from(&config.ltc_macaroon_dir)) {
-        Ok(macaroon) => hex::encode(macaroon),
+    health::check(&mut registry, config.strict_startup, config.allow_mainnet).await;
+
+    let (storage, cache) = match Storage::open(&config.claims_file) {
+        Ok(storage) => storage,
         Err(error) => {
-            panic!("LTC admin.macaroon file not found!\nERROR: {:#?}", error);
+            panic!(
+                "Couldn't open claims file {}!\nERROR: {:#?}",
+                config.claims_file, error
+            );
         }
     };
 
-    let macaroon = Macaroon {
-        btc: btc_macaroon,
-        ltc: ltc_macaroon,
-    };
-
-    let eth_wallet = MnemonicBuilder::<English>::default()
-        .phrase(&*config.eth_mnemonic)
-        .build()
-        .unwrap();
-
     let faucet = Faucet {
         config: config.clone(),
-        cache: Mutex::new(HashMap::new()),
-        macaroon,
-        eth_wallet,
+        cache: Mutex::new(cache),
+        storage: Mutex::new(storage),
+        backends: Arc::new(registry.backends),
+        lnd_nodes: Arc::new(registry.lnd_nodes),
+        started: AtomicBool::new(false),
     };
 
-    let intents =
-        GatewayIntents::GUILD_MESSAGES | GatewayIntents::GUILDS | GatewayIntents::MESSAGE_CONTENT;
+    // the legacy COIN-address messages need the privileged MESSAGE_CONTENT intent
+    let intents = if config.text_commands {
+        GatewayIntents::GUILD_MESSAGES | GatewayIntents::GUILDS | GatewayIntents::MESSAGE_CONTENT
+    } else {
+        GatewayIntents::GUILDS
+    };
     let mut client = Client::builder(&config.token, intents)
         .event_handler(faucet)
         .await
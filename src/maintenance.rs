@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+
+use crate::errors::Error;
+use crate::lightning_structs::{SendCoinsRequest, Utxo};
+use crate::lnd::LndNode;
+use crate::Faucet;
+
+// smaller fan-out outputs would be close to dust themselves
+const MIN_OUTPUT: i64 = 10_000;
+
+/// Sweeps the node's dust into one output and splits its balance into
+/// `fanout_outputs` equal outputs when it's running out of them, so payouts
+/// don't have to wait on each other's change.
+async fn maintain(node: &LndNode) -> Result<Vec<String>, Error> {
+    let mut done = Vec::new();
+    let mut utxos = node.list_unspent().await?.utxos;
+
+    if let Some(threshold) = node.config.consolidate_below_sat {
+        let (dust, rest): (Vec<Utxo>, Vec<Utxo>) = utxos
+            .into_iter()
+            .partition(|utxo| utxo.amount_sat < threshold);
+        utxos = rest;
+
+        if dust.len() > 1 {
+            done.push(consolidate(node, dust).await?);
+        }
+    }
+
+    if let Some(outputs) = node.config.fanout_outputs {
+        if let Some(fan_out) = fan_out(node, &utxos, outputs).await? {
+            done.push(fan_out);
+        }
+    }
+
+    Ok(done)
+}
+
+async fn consolidate(node: &LndNode, dust: Vec<Utxo>) -> Result<String, Error> {
+    let address = node.new_address().await?;
+    let total: i64 = dust.iter().map(|utxo| utxo.amount_sat).sum();
+
+    let rate = node
+        .estimate_fee(&HashMap::from([(address.to_owned(), total)]))
+        .await?
+        .sat_per_vbyte;
+    if rate > node.config.consolidate_max_sat_per_vbyte {
+        return Ok(format!(
+            "fees at {} sat/vbyte, left {} dust outputs alone",
+            rate,
+            dust.len()
+        ));
+    }
+
+    let request = SendCoinsRequest {
+        addr: address,
+        send_all: true,
+        sat_per_vbyte: rate,
+        outpoints: dust.iter().map(|utxo| utxo.outpoint.clone()).collect(),
+        label: String::from("faucet consolidation"),
+        ..Default::default()
+    };
+    let message = node.send_coins(&request).await?;
+
+    Ok(format!(
+        "swept {} dust outputs ({} sat) in {}",
+        dust.len(),
+        total,
+        message.txid
+    ))
+}
+
+async fn fan_out(node: &LndNode, utxos: &[Utxo], outputs: u32) -> Result<Option<String>, Error> {
+    // still plenty to pay from in parallel
+    if utxos.len() as u32 * 2 >= outputs {
+        return Ok(None);
+    }
+
+    // one share stays behind as change, the fee comes out of it
+    let total: i64 = utxos.iter().map(|utxo| utxo.amount_sat).sum();
+    let size = total / (outputs as i64 + 1);
+    if size < MIN_OUTPUT {
+        return Ok(Some(format!(
+            "balance too low to fan out into {} outputs",
+            outputs
+        )));
+    }
+
+    let mut targets = HashMap::new();
+    for _ in 0..outputs {
+        targets.insert(node.new_address().await?, size);
+    }
+    let (txid, _) = node.pay(&targets, "faucet fan-out").await?;
+
+    Ok(Some(format!(
+        "fanned out into {} outputs of {} sat in {}",
+        outputs, size, txid
+    )))
+}
+
+/// Runs maintenance on every node, one report line per node
+pub async fn run(nodes: &HashMap<String, Arc<LndNode>>) -> String {
+    let mut names: Vec<&String> = nodes.keys().collect();
+    names.sort();
+
+    let mut lines = Vec::new();
+    for name in names {
+        let line = match maintain(&nodes[name]).await {
+            Ok(done) if done.is_empty() => String::from("nothing to do"),
+            Ok(done) => done.join(", "),
+            Err(error) => format!("failed ({})", error),
+        };
+        lines.push(format!("lnd {}: {}", name, line));
+    }
+
+    if lines.is_empty() {
+        return String::from("No LND nodes to maintain!");
+    }
+
+    lines.join("\n")
+}
+
+/// Runs maintenance every `interval` and posts the report to the admin channel, if any
+pub async fn schedule(
+    nodes: Arc<HashMap<String, Arc<LndNode>>>,
+    http: Arc<Http>,
+    channel: Option<ChannelId>,
+    interval: Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let report = run(&nodes).await;
+        println!("{}", report);
+
+        if let Some(channel) = channel {
+            if let Err(why) = channel.say(&http, &report).await {
+                println!("Error sending maintenance report: {:?}", why);
+            }
+        }
+    }
+}
+
+impl Faucet {
+    pub async fn maintenance(&self) -> String {
+        run(&self.lnd_nodes).await
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::backend::Endpoint;
+use crate::errors::Error;
+use crate::lnd::{parse_label, LndNode};
+use crate::storage::{self, Record};
+use crate::utils::claim_id;
+use crate::Faucet;
+
+// problems listed per kind and node, discord cuts messages at 2000 characters
+const MAX_LISTED: usize = 5;
+
+/// How the claims paid out through one node line up with its wallet history
+#[derive(Default)]
+struct Report {
+    labelled: usize,
+    unlabelled: Vec<String>, // the claim's transaction doesn't carry its id
+    missing: Vec<String>,    // the claim's transaction isn't in the wallet
+    unclaimed: Vec<String>,  // labelled with a claim the claims file doesn't have
+}
+
+/// Claims with an on-chain payout: claim id -> txid, and the ids of every claim,
+/// paid out or released, to tell labels written by the faucet from unknown ones
+fn claims(records: &[Record], coins: &HashSet<String>) -> (Vec<(String, String)>, HashSet<String>) {
+    let released: HashSet<(u64, &str, u64)> = records
+        .iter()
+        .filter_map(|record| match record {
+            Record::Release {
+                user_id,
+                coin,
+                timestamp,
+            } => Some((*user_id, coin.as_str(), *timestamp)),
+            _ => None,
+        })
+        .collect();
+
+    let mut paid = Vec::new();
+    let mut known = HashSet::new();
+    for record in records {
+        match record {
+            Record::Claim {
+                user_id,
+                coin,
+                timestamp,
+            } if coins.contains(coin) => {
+                known.insert(claim_id(*user_id, coin, *timestamp));
+            }
+            // lightning payments and claims from before labelling have no txid
+            Record::Paid {
+                user_id,
+                coin,
+                timestamp,
+                txid,
+            } if coins.contains(coin)
+                && !released.contains(&(*user_id, coin.as_str(), *timestamp)) =>
+            {
+                paid.push((claim_id(*user_id, coin, *timestamp), txid.to_owned()));
+            }
+            _ => {}
+        }
+    }
+
+    (paid, known)
+}
+
+async fn reconcile(
+    node: &LndNode,
+    records: &[Record],
+    coins: &HashSet<String>,
+) -> Result<Report, Error> {
+    let (paid, known) = claims(records, coins);
+
+    let transactions = node.get_transactions(0).await?.transactions;
+
+    // txid -> ids of the claims its label names
+    let mut labels: HashMap<&str, HashSet<String>> = HashMap::new();
+    let mut report = Report::default();
+    for tx in transactions.iter() {
+        let tags = match parse_label(&tx.label) {
+            Some(tags) => tags,
+            None => continue,
+        };
+        for tag in tags {
+            if coins.contains(&tag.coin) && !known.contains(&tag.id) {
+                report
+                    .unclaimed
+                    .push(format!("{} {} ({})", tag.coin, tag.id, tx.tx_hash));
+            }
+            labels.entry(&tx.tx_hash).or_default().insert(tag.id);
+        }
+    }
+
+    for (id, txid) in paid {
+        if labels
+            .get(txid.as_str())
+            .is_some_and(|ids| ids.contains(&id))
+        {
+            report.labelled += 1;
+        } else if transactions.iter().any(|tx| tx.tx_hash == txid) {
+            report.unlabelled.push(format!("{} ({})", id, txid));
+        } else {
+            report.missing.push(format!("{} ({})", id, txid));
+        }
+    }
+
+    Ok(report)
+}
+
+fn list(lines: &mut Vec<String>, what: &str, entries: &[String]) {
+    for entry in entries.iter().take(MAX_LISTED) {
+        lines.push(format!("  {}: {}", what, entry));
+    }
+    if entries.len() > MAX_LISTED {
+        lines.push(format!("  ... and {} more", entries.len() - MAX_LISTED));
+    }
+}
+
+impl Faucet {
+    /// Joins the claim labels of every LND node's wallet history against the claims file
+    pub async fn reconcile(&self) -> String {
+        let records = {
+            // keeps appends out while the file is read
+            let _storage = self.storage.lock().await;
+            match storage::read(&self.config.claims_file) {
+                Ok(records) => records,
+                Err(error) => return format!("Couldn't read the claims file: {}", error),
+            }
+        };
+
+        let mut names: Vec<&String> = self.lnd_nodes.keys().collect();
+        names.sort();
+
+        let mut lines = Vec::new();
+        for name in names {
+            let endpoint = Endpoint::Lnd(name.to_owned());
+            let coins: HashSet<String> = self
+                .backends
+                .iter()
+                .filter(|(_, backend)| backend.endpoint() == endpoint)
+                .map(|(coin, _)| coin.to_owned())
+                .collect();
+
+            let report = match reconcile(&self.lnd_nodes[name], &records, &coins).await {
+                Ok(report) => report,
+                Err(error) => {
+                    lines.push(format!("{}: failed ({})", endpoint, error));
+                    continue;
+                }
+            };
+
+            lines.push(format!(
+                "{}: {} claims labelled, {} unlabelled, {} missing from the wallet, {} labels without a claim",
+                endpoint,
+                report.labelled,
+                report.unlabelled.len(),
+                report.missing.len(),
+                report.unclaimed.len()
+            ));
+            list(&mut lines, "unlabelled", &report.unlabelled);
+            list(&mut lines, "missing", &report.missing);
+            list(&mut lines, "no claim", &report.unclaimed);
+        }
+
+        if lines.is_empty() {
+            return String::from("No LND nodes to reconcile!");
+        }
+
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use serenity::model::prelude::UserId;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::errors::Error;
+use crate::structs::Cache;
+
+/// A single entry of the append-only claims file, one json object per line
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Claim {
+        user_id: u64,
+        coin: String,
+        timestamp: u64,
+    },
+    // the claim's on-chain payout went out in `txid`
+    Paid {
+        user_id: u64,
+        coin: String,
+        timestamp: u64,
+        txid: String,
+    },
+    // the claim's payout reverted or never made it on chain, its cooldown no longer applies
+    Release {
+        user_id: u64,
+        coin: String,
+        timestamp: u64,
+    },
+    // the claim's payout `txid` got stuck and had its fee bumped by `replacement`
+    Bump {
+        user_id: u64,
+        coin: String,
+        timestamp: u64,
+        txid: String,
+        replacement: String,
+    },
+}
+
+pub struct Storage {
+    file: File,
+}
+
+impl Storage {
+    /// Opens (or creates) the claims file and replays it into a fresh cache
+    pub fn open(path: &str) -> Result<(Self, Cache), Error> {
+        let mut cache: Cache = HashMap::new();
+        for record in read(path)? {
+            apply(&mut cache, record);
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        // end a line truncated by a crash, or the next record would be glued onto it
+        if file.metadata()?.len() > 0 {
+            let mut last = [0u8];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                file.sync_data()?;
+            }
+        }
+
+        Ok((Storage { file }, cache))
+    }
+
+    /// Appends a record and waits for it to hit the disk
+    pub fn append(&mut self, record: &Record) -> Result<(), Error> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Every record in the claims file, in the order they were written
+pub fn read(path: &str) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    if !Path::new(path).exists() {
+        return Ok(records);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // a crash mid-write can leave a truncated last line behind, skip it
+        match serde_json::from_str::<Record>(&line) {
+            Ok(record) => records.push(record),
+            Err(error) => println!("Skipping {} line {}: {}", path, index + 1, error),
+        }
+    }
+
+    Ok(records)
+}
+
+fn apply(cache: &mut Cache, record: Record) {
+    match record {
+        Record::Claim {
+            user_id,
+            coin,
+            timestamp,
+        } => {
+            let user = cache.entry(UserId(user_id)).or_default();
+            let last = user.entry(coin).or_default();
+            if timestamp > *last {
+                *last = timestamp;
+            }
+        }
+        Record::Release {
+            user_id,
+            coin,
+            timestamp,
+        } => {
+            let user = cache.entry(UserId(user_id)).or_default();
+            if user.get(&coin) == Some(&timestamp) {
+                user.remove(&coin);
+            }
+        }
+        Record::Paid { .. } | Record::Bump { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(user_id: u64, coin: &str, timestamp: u64) -> Record {
+        Record::Claim {
+            user_id,
+            coin: coin.to_owned(),
+            timestamp,
+        }
+    }
+
+    fn release(user_id: u64, coin: &str, timestamp: u64) -> Record {
+        Record::Release {
+            user_id,
+            coin: coin.to_owned(),
+            timestamp,
+        }
+    }
+
+    fn replay(records: Vec<Record>) -> Cache {
+        let mut cache: Cache = HashMap::new();
+        for record in records {
+            apply(&mut cache, record);
+        }
+        cache
+    }
+
+    #[test]
+    fn release_lifts_the_claim() {
+        let cache = replay(vec![claim(1, "BTC", 1000), release(1, "BTC", 1000)]);
+        assert_eq!(cache[&UserId(1)].get("BTC"), None);
+    }
+
+    #[test]
+    fn release_of_an_expired_claim_keeps_the_newer_one() {
+        let cache = replay(vec![
+            claim(1, "BTC", 1000),
+            claim(1, "BTC", 1000 + 25 * 3600),
+            release(1, "BTC", 1000),
+        ]);
+        assert_eq!(cache[&UserId(1)].get("BTC"), Some(&(1000 + 25 * 3600)));
+    }
+
+    #[test]
+    fn expired_claim_is_replayed_as_is() {
+        // expiry is up to reserve, replay only keeps the latest claim per coin
+        let cache = replay(vec![claim(1, "BTC", 1000), claim(1, "BTC", 500)]);
+        assert_eq!(cache[&UserId(1)].get("BTC"), Some(&1000));
+        assert_eq!(cache[&UserId(1)].get("LTC"), None);
+    }
+
+    #[test]
+    fn skips_garbage_and_truncated_lines() {
+        let path = std::env::temp_dir().join(format!("claims-{}.jsonl", std::process::id()));
+        let lines = [
+            r#"{"type":"claim","user_id":1,"coin":"BTC","timestamp":1000}"#,
+            "not json at all",
+            "",
+            r#"{"type":"paid","user_id":1,"coin":"BTC","timestamp":1000,"txid":"ab"}"#,
+            r#"{"type":"claim","user_id":2,"coin":"ET"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let records = read(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(records.len(), 2);
+        let cache = replay(records);
+        assert_eq!(cache[&UserId(1)].get("BTC"), Some(&1000));
+        assert!(!cache.contains_key(&UserId(2)));
+    }
+
+    #[test]
+    fn appends_after_a_truncated_line() {
+        let path = std::env::temp_dir().join(format!("claims-tail-{}.jsonl", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let lines = [
+            r#"{"type":"claim","user_id":1,"coin":"BTC","timestamp":1000}"#,
+            r#"{"type":"claim","user_id":2,"coin":"ET"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let (mut storage, _) = Storage::open(path_str).unwrap();
+        storage.append(&claim(3, "LTC", 2000)).unwrap();
+        drop(storage);
+
+        let records = read(path_str).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(records.len(), 2);
+        let cache = replay(records);
+        assert_eq!(cache[&UserId(1)].get("BTC"), Some(&1000));
+        assert_eq!(cache[&UserId(3)].get("LTC"), Some(&2000));
+    }
+}
@@ -1,61 +1,189 @@
-use serde::Deserialize;
+use ethers::types::U256;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_aux::prelude::*;
 use serenity::model::prelude::UserId;
-use std::{collections::HashMap, fmt::Display};
+use std::collections::HashMap;
 
 #[derive(Default, Clone, Deserialize)]
 pub struct Config {
     pub token: String,
+    pub guild_id: Option<u64>,
+    #[serde(default)]
+    pub text_commands: bool,
     pub eth_mnemonic: String,
-    pub btc_url: String,
-    pub ltc_url: String,
-    pub btc_macaroon_dir: String,
-    pub ltc_macaroon_dir: String,
     pub limit: u64,
-    pub providers: HashMap<String, String>,
+    #[serde(default = "default_channel_limit")]
+    pub channel_limit: u64, // hours, channel requests have a cooldown of their own
+    #[serde(default = "default_claims_file")]
+    pub claims_file: String,
+    #[serde(default)]
+    pub strict_startup: bool,
+    #[serde(default)]
+    pub allow_mainnet: bool,
+    pub admin_channel_id: Option<u64>,
+    #[serde(default = "default_low_balance_payouts")]
+    pub low_balance_payouts: u64,
+    #[serde(
+        default = "default_balance_interval",
+        deserialize_with = "deserialize_interval"
+    )]
+    pub balance_interval: u64, // minutes
+    #[serde(default, deserialize_with = "deserialize_optional_interval")]
+    pub maintenance_interval: Option<u64>, // hours between scheduled wallet maintenance runs
+    #[serde(default)]
+    pub label_secret: String, // keys the requester hashes in LND labels
+    #[serde(default)]
+    pub lnd: HashMap<String, ConfigLnd>,
+    #[serde(default)]
+    pub networks: HashMap<String, ConfigNetwork>,
     pub coins: HashMap<String, ConfigCoin>,
 }
 
+fn default_claims_file() -> String {
+    String::from("claims.jsonl")
+}
+
+fn default_channel_limit() -> u64 {
+    168
+}
+
+fn default_low_balance_payouts() -> u64 {
+    10
+}
+
+fn default_balance_interval() -> u64 {
+    10
+}
+
+// a zero interval would have the background task loop without pausing
+fn deserialize_interval<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let interval = u64::deserialize(deserializer)?;
+    if interval == 0 {
+        return Err(D::Error::custom("intervals must be at least 1"));
+    }
+    Ok(interval)
+}
+
+// leaving the key out turns the task off, 0 is refused rather than read as off
+fn deserialize_optional_interval<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    match Option::<u64>::deserialize(deserializer)? {
+        Some(0) => Err(D::Error::custom(
+            "intervals must be at least 1, leave the key out to turn it off",
+        )),
+        interval => Ok(interval),
+    }
+}
+
+pub const LIGHTNING: &str = "lightning";
+
+#[derive(Clone, Deserialize)]
+pub struct ConfigLnd {
+    pub url: String, // REST https://host:port
+    pub macaroon_path: String,
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub insecure_tls: bool,
+    #[serde(default)]
+    pub target_conf: i32, // 0 leaves it to lnd
+    #[serde(default)]
+    pub sat_per_vbyte: u64, // 0 leaves it to lnd
+    #[serde(default)]
+    pub min_confs: i32, // 0 leaves it to lnd
+    #[serde(default)]
+    pub spend_unconfirmed: bool,
+    pub max_sat_per_vbyte: Option<u64>, // sends are refused above this fee rate
+    pub batch_window: Option<u64>,      // seconds on-chain payouts are collected for one SendMany
+    #[serde(default = "default_max_routing_fee")]
+    pub max_routing_fee_sat: i64, // per lightning invoice paid
+    pub channel_capacity: Option<i64>,  // sats, channel requests are refused without it
+    #[serde(default)]
+    pub channel_push_sat: i64,
+    pub fanout_outputs: Option<u32>, // keep about this many spendable outputs around
+    pub consolidate_below_sat: Option<i64>, // outputs smaller than this count as dust
+    #[serde(default = "default_consolidate_fee")]
+    pub consolidate_max_sat_per_vbyte: u64, // dust is only swept at or below this fee rate
+    pub bump_after: Option<u64>, // minutes a payout may stay unconfirmed before its fee is bumped
+    #[serde(default = "default_bump_budget")]
+    pub bump_budget_sat: u64, // most a single bump may spend on fees
+}
+
+fn default_max_routing_fee() -> i64 {
+    100
+}
+
+fn default_consolidate_fee() -> u64 {
+    2
+}
+
+fn default_bump_budget() -> u64 {
+    5000
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ConfigNetwork {
+    pub providers: Vec<String>,
+    pub chain_id: u64,
+    #[serde(default)]
+    pub testnet: bool, // for test chains that aren't on the built-in list
+    pub explorer_tx: Option<String>,
+    pub explorer_address: Option<String>,
+    pub native_symbol: String,
+    #[serde(default)]
+    pub fee_mode: FeeMode,
+    pub bump_after: Option<u64>, // minutes a payout may stay unconfirmed before it's replaced
+    #[serde(
+        default = "default_bump_max_fee",
+        deserialize_with = "deserialize_string_from_number"
+    )]
+    pub bump_max_fee: String, // native coin, gas limit * max fee per gas of a replacement
+}
+
+fn default_bump_max_fee() -> String {
+    String::from("0.01")
+}
+
+#[derive(Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeMode {
+    #[default]
+    Eip1559,
+    Legacy,
+}
+
 #[derive(Clone, Deserialize)]
 pub struct ConfigCoin {
-    pub amount: f64,
-    pub network: Network,
+    #[serde(deserialize_with = "deserialize_string_from_number")]
+    pub amount: String,
+    pub network: String,
+    pub kind: CoinKind,
+    pub node: Option<String>, // [lnd.<name>] paying out a lightning coin
     #[serde(default)]
     pub contract: String,
     pub decimals: u32,
+    pub explorer_tx: Option<String>,
+    pub explorer_address: Option<String>,
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinKind {
+    Native,
+    Erc20,
 }
 
 #[derive(Clone)]
 pub struct Coin {
     pub name: String,
-    pub amount: f64,
-    pub network: Network,
+    pub amount: U256, // base units
+    pub network: String,
+    pub kind: CoinKind,
     pub contract: String,
     pub decimals: u32,
-}
-
-#[derive(Clone, Deserialize, Hash, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum Network {
-    Lightning,
-    Ethereum,
-    Arbitrum,
-}
-
-impl Display for Network {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Network::Lightning => write!(f, "lightning"),
-            Network::Ethereum => write!(f, "ethereum"),
-            Network::Arbitrum => write!(f, "arbitrum"),
-        }
-    }
-}
-
-#[derive(Default, Clone, Deserialize)]
-#[serde(default)]
-pub struct Macaroon {
-    pub btc: String,
-    pub ltc: String,
+    pub explorer_tx: Option<String>,      // "{txid}" is replaced
+    pub explorer_address: Option<String>, // "{address}" is replaced
 }
 
 pub type Cache = HashMap<UserId, HashMap<String, u64>>; // user_id -> coin -> timestamp
@@ -0,0 +1,262 @@
+use std::time::{Duration, Instant};
+
+use serenity::http::Http;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::model::prelude::UserId;
+use serenity::utils::MessageBuilder;
+
+use crate::backend::{Bumped, Confirmation, PaymentBackend};
+use crate::errors::Error;
+use crate::storage::Record;
+use crate::Faucet;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// a payout the node hasn't heard of for this many polls in a row is considered dropped
+const DROPPED_AFTER: u32 = 20;
+// stop watching after a day, whatever happened by then
+const MAX_POLLS: u32 = 2880;
+// interaction tokens expire after 15 minutes, switch to a message of our own a bit before
+const TOKEN_LIFETIME: Duration = Duration::from_secs(14 * 60);
+
+pub enum PayoutKind {
+    Transaction,
+    Channel,
+}
+
+/// A payout that went out and still has to confirm
+pub struct Payout {
+    pub user_id: UserId,
+    pub coin: String,
+    pub kind: PayoutKind,
+    pub txid: String,     // channel point for channels
+    pub cooldown: String, // what the claim was reserved under
+    pub timestamp: u64,   // of the claim, to undo its cooldown
+}
+
+/// The message the bot answered a request with
+pub enum Reply {
+    Interaction {
+        command: ApplicationCommandInteraction,
+        created: Instant,
+    },
+    Message(Message),
+}
+
+impl Reply {
+    async fn edit(&mut self, http: &Http, content: String) {
+        let result = match self {
+            Reply::Interaction { command, created } => {
+                if created.elapsed() < TOKEN_LIFETIME {
+                    match command
+                        .edit_original_interaction_response(http, |response| {
+                            response.content(&content)
+                        })
+                        .await
+                    {
+                        Ok(_) => return,
+                        Err(why) => println!("Error editing reply: {:?}", why),
+                    }
+                }
+
+                // the token expired, carry on in a message of our own that can be edited for good
+                repost(http, command.user.id, command.channel_id, &content)
+                    .await
+                    .map(|message| *self = Reply::Message(message))
+            }
+            Reply::Message(message) => message.edit(http, |message| message.content(content)).await,
+        };
+
+        if let Err(why) = result {
+            println!("Error editing reply: {:?}", why);
+        }
+    }
+
+    /// Sends `content` as a message of its own, edits don't notify anyone
+    async fn notify(&self, http: &Http, user_id: UserId, content: &str) {
+        let result = match self {
+            Reply::Interaction { command, .. } => {
+                repost(http, user_id, command.channel_id, content).await
+            }
+            Reply::Message(message) => {
+                let content = MessageBuilder::new()
+                    .mention(&user_id)
+                    .push(" ")
+                    .push(content)
+                    .build();
+                message.channel_id.say(http, content).await
+            }
+        };
+
+        if let Err(why) = result {
+            println!("Error sending notice: {:?}", why);
+        }
+    }
+}
+
+/// DMs `content` to the user, or posts it in `channel_id` if their DMs are closed
+async fn repost(
+    http: &Http,
+    user_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+) -> Result<Message, serenity::Error> {
+    let dm = match user_id.create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, content).await,
+        Err(why) => Err(why),
+    };
+
+    match dm {
+        Ok(message) => Ok(message),
+        Err(why) => {
+            println!("Couldn't DM {}: {:?}", user_id, why);
+            let content = MessageBuilder::new()
+                .mention(&user_id)
+                .push(" ")
+                .push(content)
+                .build();
+            channel_id.say(http, content).await
+        }
+    }
+}
+
+/// Status of a payout that may have been replaced, whichever of its transactions made it
+async fn confirmation(
+    backend: &dyn PaymentBackend,
+    txids: &[String],
+) -> Result<Confirmation, Error> {
+    for txid in txids.iter().rev() {
+        match backend.confirmation(txid).await? {
+            Confirmation::Unknown => continue,
+            status => return Ok(status),
+        }
+    }
+    Ok(Confirmation::Unknown)
+}
+
+impl Faucet {
+    /// Polls the payout until it confirms, reverts or disappears and appends the outcome to `reply`.
+    /// Reverted and dropped payouts give the user their cooldown back.
+    /// Payouts pending for longer than the backend's bump_after get their fee bumped.
+    pub async fn track(&self, http: &Http, mut reply: Reply, mut content: String, payout: Payout) {
+        let backend = match self.backends.get(&payout.coin) {
+            Some(backend) => backend,
+            None => return,
+        };
+
+        // the payout and the replacements it got since, newest last
+        let mut txids = vec![payout.txid.to_owned()];
+        let mut last_sent = Instant::now();
+
+        let mut unknown = 0;
+        for _ in 0..MAX_POLLS {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let result = match payout.kind {
+                PayoutKind::Transaction => confirmation(backend.as_ref(), &txids).await,
+                PayoutKind::Channel => backend.channel_status(&payout.txid).await,
+            };
+
+            let status = match result {
+                Ok(Confirmation::Pending) => {
+                    unknown = 0;
+
+                    let stuck = backend
+                        .bump_after()
+                        .is_some_and(|after| last_sent.elapsed() >= after);
+                    if matches!(payout.kind, PayoutKind::Transaction) && stuck {
+                        last_sent = Instant::now();
+
+                        let txid = txids.last().cloned().unwrap_or_default();
+                        if let Some(bumped) = self.bump(backend.as_ref(), &payout, &txid).await {
+                            let link = backend
+                                .explorer_url(&bumped.txid)
+                                .unwrap_or_else(|| bumped.txid.to_owned());
+                            let note = if bumped.replaces {
+                                txids.push(bumped.txid);
+                                format!("⏫ fee bumped, replaced by {}", link)
+                            } else {
+                                format!("⏫ fee bumped by child tx {}", link)
+                            };
+
+                            content = format!("{}\n{}", content, note);
+                            reply.edit(http, content.to_owned()).await;
+                        }
+                    }
+                    continue;
+                }
+                Ok(Confirmation::Unknown) => {
+                    unknown += 1;
+                    if unknown < DROPPED_AFTER {
+                        continue;
+                    }
+                    self.release(payout.user_id, &payout.cooldown, payout.timestamp)
+                        .await;
+                    String::from("❌ dropped, you can request again")
+                }
+                Ok(Confirmation::Confirmed(block)) => match payout.kind {
+                    PayoutKind::Transaction => format!("✅ confirmed at block {}", block),
+                    PayoutKind::Channel => format!("✅ channel active, funded at block {}", block),
+                },
+                Ok(Confirmation::Reverted(block)) => {
+                    self.release(payout.user_id, &payout.cooldown, payout.timestamp)
+                        .await;
+                    format!("❌ reverted at block {}, you can request again", block)
+                }
+                Err(error) => {
+                    println!("Couldn't check {} {}: {}", payout.coin, payout.txid, error);
+                    continue;
+                }
+            };
+
+            println!("{} {}: {}", payout.coin, payout.txid, status);
+            match payout.kind {
+                PayoutKind::Transaction => {
+                    reply.edit(http, format!("{}\n{}", content, status)).await
+                }
+                // channels take many blocks to open, long after the user stopped looking
+                PayoutKind::Channel => {
+                    let notice = format!("{} channel {}: {}", payout.coin, payout.txid, status);
+                    reply.notify(http, payout.user_id, &notice).await
+                }
+            }
+            return;
+        }
+    }
+
+    /// Bumps the fee of `txid` and records the bump against the payout's claim
+    async fn bump(
+        &self,
+        backend: &dyn PaymentBackend,
+        payout: &Payout,
+        txid: &str,
+    ) -> Option<Bumped> {
+        let bumped = match backend.bump(txid).await {
+            Ok(Some(bumped)) => bumped,
+            Ok(None) => {
+                println!("{} {}: pending, can't bump", payout.coin, txid);
+                return None;
+            }
+            Err(error) => {
+                println!("Couldn't bump {} {}: {}", payout.coin, txid, error);
+                return None;
+            }
+        };
+        println!("{} {}: fee bumped by {}", payout.coin, txid, bumped.txid);
+
+        let record = Record::Bump {
+            user_id: payout.user_id.0,
+            coin: payout.cooldown.to_owned(),
+            timestamp: payout.timestamp,
+            txid: txid.to_owned(),
+            replacement: bumped.txid.to_owned(),
+        };
+
+        if let Err(error) = self.storage.lock().await.append(&record) {
+            println!("Failed to persist bump: {}", error);
+        }
+
+        Some(bumped)
+    }
+}